@@ -1,3 +1,99 @@
-fn main() {
-    println!("Moss Lang v{}", env!("CARGO_PKG_VERSION"));
+use std::{path::PathBuf, process::ExitCode};
+
+use clap::{Parser, Subcommand};
+use moss_interpreter::interpreter::{Interpreter, InterpreterLike as _, file::FileId};
+
+mod check;
+mod eval;
+mod fmt;
+
+#[derive(Parser)]
+#[command(name = "moss", version, about = "Moss Lang")]
+struct Cli {
+    #[command(subcommand)]
+    command: Option<Command>,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Run a workspace and print its diagnostics, failing if there is any error.
+    Check {
+        /// Workspace directory, the one containing `src/`.
+        #[arg(default_value = ".")]
+        workspace: PathBuf,
+        #[arg(long, value_enum, default_value_t)]
+        format: check::Format,
+    },
+    /// Run a workspace and print resolved values of its elements.
+    Eval {
+        /// Workspace directory, the one containing `src/`.
+        workspace: PathBuf,
+        /// Dotted path of an element from a module's root scope, like `a.j`.
+        /// All root scope elements are printed if omitted.
+        path: Option<String>,
+    },
+    /// Format the source files of a workspace in place.
+    Fmt {
+        /// Workspace directory, the one containing `src/`.
+        #[arg(default_value = ".")]
+        workspace: PathBuf,
+        /// List unformatted files without writing them, failing if there is any.
+        #[arg(long)]
+        check: bool,
+    },
+}
+
+/// Adds every module under the workspace's `src/` and runs them to the end.
+///
+/// # Returns
+/// - `None` if the workspace has no `src/` directory.
+async fn run_workspace(workspace: PathBuf) -> Option<Interpreter> {
+    let mut interpreter = Interpreter::new(workspace);
+    if !interpreter.get_src_path().is_dir() {
+        eprintln!(
+            "error: no `src` directory in workspace {}",
+            interpreter.workspace_path.display()
+        );
+        return None;
+    }
+    interpreter.init();
+    interpreter.add_src_modules();
+    interpreter.run().await;
+    Some(interpreter)
+}
+
+/// Files loaded as modules, sorted by path.
+fn module_files(interpreter: &Interpreter) -> Vec<FileId> {
+    let mut files = interpreter
+        .files
+        .iter()
+        .filter(|(_, file)| file.is_module.is_some())
+        .map(|(id, _)| id)
+        .collect::<Vec<_>>();
+    files.sort_by(|a, b| interpreter.files[*a].path.cmp(&interpreter.files[*b].path));
+    files
+}
+
+#[tokio::main]
+async fn main() -> ExitCode {
+    let cli = Cli::parse();
+    match cli.command {
+        Some(Command::Check { workspace, format }) => {
+            let Some(interpreter) = run_workspace(workspace).await else {
+                return ExitCode::FAILURE;
+            };
+            check::check(&interpreter, format)
+        }
+        Some(Command::Eval { workspace, path }) => {
+            let Some(mut interpreter) = run_workspace(workspace).await else {
+                return ExitCode::FAILURE;
+            };
+            eval::eval(&mut interpreter, path.as_deref())
+        }
+        Some(Command::Fmt { workspace, check }) => fmt::fmt(&workspace, check),
+        None => {
+            println!("Moss Lang v{}", env!("CARGO_PKG_VERSION"));
+            ExitCode::SUCCESS
+        }
+    }
 }
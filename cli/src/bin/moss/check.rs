@@ -0,0 +1,61 @@
+use std::process::ExitCode;
+
+use clap::ValueEnum;
+use moss_interpreter::interpreter::{Interpreter, diagnose::Severity};
+
+use crate::module_files;
+
+#[derive(Clone, Copy, Debug, Default, ValueEnum)]
+pub enum Format {
+    /// `path:line:col: severity[code]: message`, one diagnostic a line.
+    #[default]
+    Text,
+    /// A JSON array of diagnostic records.
+    Json,
+}
+
+/// Prints every diagnostic of the workspace in `format`.
+///
+/// # Returns
+/// - failure if there is any error.
+pub fn check(interpreter: &Interpreter, format: Format) -> ExitCode {
+    let records = module_files(interpreter)
+        .into_iter()
+        .flat_map(|file_id| interpreter.file_records(file_id))
+        .collect::<Vec<_>>();
+    match format {
+        Format::Text => {
+            for record in &records {
+                println!(
+                    "{}:{}:{}: {}[{}]: {}",
+                    interpreter.workspace_path.join(&record.file).display(),
+                    record.range.start.line + 1,
+                    record.range.start.column + 1,
+                    record.severity,
+                    record.code,
+                    record.message
+                );
+                for related in &record.related {
+                    println!(
+                        "    {}:{}:{}: note: {}",
+                        interpreter.workspace_path.join(&related.file).display(),
+                        related.range.start.line + 1,
+                        related.range.start.column + 1,
+                        related.message
+                    );
+                }
+            }
+        }
+        Format::Json => println!("{}", serde_json::to_string_pretty(&records).unwrap()),
+    }
+    let error_count = records
+        .iter()
+        .filter(|x| x.severity == Severity::Error)
+        .count();
+    if error_count > 0 {
+        eprintln!("{} error(s)", error_count);
+        ExitCode::FAILURE
+    } else {
+        ExitCode::SUCCESS
+    }
+}
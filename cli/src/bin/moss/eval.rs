@@ -0,0 +1,100 @@
+use std::process::ExitCode;
+
+use moss_interpreter::{
+    interpreter::{
+        Id, Interpreter, InterpreterLike as _, InterpreterLikeMut as _,
+        element::Element,
+        scope::Scope,
+        value::{self, Value},
+    },
+    utils::{concurrent_string_interner::StringId, contexted::WithContext as _},
+};
+
+use crate::module_files;
+
+/// Prints the value of the element at the dotted `path`, looked up from the root scope of
+/// every module. Without `path`, prints every root scope element of every module instead.
+///
+/// # Returns
+/// - failure if `path` is not found in any module.
+pub fn eval(interpreter: &mut Interpreter, path: Option<&str>) -> ExitCode {
+    let files = module_files(interpreter);
+    let Some(path) = path else {
+        for file_id in files {
+            let file = interpreter.get_file(file_id);
+            let Some(scope_id) = interpreter.get_module_scope(file.is_module.unwrap()) else {
+                continue;
+            };
+            println!("# {}", file.path.display());
+            let mut elements = interpreter
+                .get(scope_id)
+                .elements
+                .iter()
+                .map(|(key, element_id)| (interpreter.id2str(*key).to_string(), *element_id))
+                .collect::<Vec<_>>();
+            elements.sort_by(|a, b| a.0.cmp(&b.0));
+            for (name, element_id) in elements {
+                println!("{} = {}", name, render(interpreter, element_id));
+            }
+        }
+        return ExitCode::SUCCESS;
+    };
+    let keys = path
+        .split('.')
+        .map(|x| interpreter.str2id(x))
+        .collect::<Vec<_>>();
+    let mut found = vec![];
+    for file_id in files {
+        let module_id = interpreter.get_file(file_id).is_module.unwrap();
+        let Some(scope_id) = interpreter.get_module_scope(module_id) else {
+            continue;
+        };
+        if let Some(element_id) = find_path(interpreter, scope_id, &keys) {
+            found.push((file_id, element_id));
+        }
+    }
+    match found.as_slice() {
+        [] => {
+            eprintln!("error: no element `{}` in any module", path);
+            ExitCode::FAILURE
+        }
+        [(_, element_id)] => {
+            println!("{}", render(interpreter, *element_id));
+            ExitCode::SUCCESS
+        }
+        found => {
+            for (file_id, element_id) in found {
+                println!(
+                    "{}: {}",
+                    interpreter.get_file(*file_id).path.display(),
+                    render(interpreter, *element_id)
+                );
+            }
+            ExitCode::SUCCESS
+        }
+    }
+}
+
+fn find_path(
+    interpreter: &Interpreter,
+    mut scope_id: Id<Scope>,
+    keys: &[StringId],
+) -> Option<Id<Element>> {
+    let (last, keys) = keys.split_last()?;
+    for key in keys {
+        let element_id = interpreter.get(scope_id).elements.get(key).copied()?;
+        scope_id = interpreter
+            .get_element_value(element_id)?
+            .as_scope()
+            .ok()?
+            .0;
+    }
+    interpreter.get(scope_id).elements.get(last).copied()
+}
+
+fn render(interpreter: &Interpreter, element_id: Id<Element>) -> String {
+    let value = interpreter
+        .get_element_value(element_id)
+        .unwrap_or(Value::Error(value::Error));
+    format!("{}", value.with_ctx(interpreter))
+}
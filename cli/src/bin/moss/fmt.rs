@@ -0,0 +1,58 @@
+use std::{fs, path::Path, process::ExitCode};
+
+use moss_interpreter::{
+    format::format,
+    interpreter::{
+        SRC_PATH,
+        source::{DiskSource, SourceProvider as _},
+    },
+};
+
+/// Formats every source file under the workspace's `src/` in place. With `check`, lists the
+/// files that aren't formatted instead of writing them.
+///
+/// # Returns
+/// - failure if the workspace has no `src/` directory, if a file can't be read, written or
+///   formatted, or with `check` if any file isn't formatted.
+pub fn fmt(workspace: &Path, check: bool) -> ExitCode {
+    if !workspace.join(SRC_PATH).is_dir() {
+        eprintln!(
+            "error: no `src` directory in workspace {}",
+            workspace.display()
+        );
+        return ExitCode::FAILURE;
+    }
+    let source = DiskSource::new(workspace.to_path_buf());
+    let mut failed = false;
+    for path in source.list() {
+        let full_path = workspace.join(&path);
+        let text = match source.read(&path) {
+            Ok(text) => text,
+            Err(error) => {
+                eprintln!("error: can not read {}: {}", full_path.display(), error);
+                failed = true;
+                continue;
+            }
+        };
+        let Some(formatted) = format(&text) else {
+            eprintln!("error: can not format {}", full_path.display());
+            failed = true;
+            continue;
+        };
+        if formatted == text {
+            continue;
+        }
+        if check {
+            println!("{}", full_path.display());
+            failed = true;
+        } else if let Err(error) = fs::write(&full_path, formatted) {
+            eprintln!("error: can not write {}: {}", full_path.display(), error);
+            failed = true;
+        }
+    }
+    if failed {
+        ExitCode::FAILURE
+    } else {
+        ExitCode::SUCCESS
+    }
+}
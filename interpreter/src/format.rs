@@ -0,0 +1,180 @@
+use tree_sitter::{Node as RawNode, Parser};
+use type_sitter::{HasChild as _, Node as _};
+
+use crate::utils::moss;
+
+const INDENT: &str = "    ";
+
+/// Formats Moss source: one assign a line, single spaces around `=` and `->`, no space before
+/// `;`, and scopes written over several lines indented by [`INDENT`]. A scope written on one
+/// line stays on one line, and at most one blank line is kept between assigns. Formatting its
+/// own output changes nothing.
+///
+/// # Returns
+/// - `None` if `text` has a syntax error, or a comment somewhere it can't be kept.
+pub fn format(text: &str) -> Option<String> {
+    let mut parser = Parser::new();
+    parser
+        .set_language(&tree_sitter_moss::LANGUAGE.into())
+        .unwrap();
+    let tree = type_sitter::Tree::<moss::SourceFile<'_>>::wrap(parser.parse(text, None)?);
+    let root = *tree.root_node().ok()?.raw();
+    if root.has_error() {
+        return None;
+    }
+    let mut formatter = Formatter {
+        text,
+        out: String::new(),
+        depth: 0,
+        comments: 0,
+    };
+    formatter.body(root)?;
+    if formatter.comments != count_comments(root) {
+        return None;
+    }
+    let out = formatter.out.trim_start();
+    if out.is_empty() {
+        return Some(String::new());
+    }
+    Some(format!("{}\n", out))
+}
+
+/// Comments anywhere under `node`, the named extras of the grammar.
+fn count_comments(node: RawNode<'_>) -> usize {
+    let mut cursor = node.walk();
+    let mut count = 0;
+    'walk: loop {
+        let node = cursor.node();
+        if node.is_extra() && node.is_named() {
+            count += 1;
+        }
+        if cursor.goto_first_child() {
+            continue;
+        }
+        while !cursor.goto_next_sibling() {
+            if !cursor.goto_parent() {
+                break 'walk;
+            }
+        }
+    }
+    count
+}
+
+struct Formatter<'a> {
+    text: &'a str,
+    out: String,
+    depth: usize,
+    /// Comments written, each one kept as is.
+    comments: usize,
+}
+
+impl<'a> Formatter<'a> {
+    fn slice(&self, node: RawNode<'_>) -> &'a str {
+        &self.text[node.byte_range()]
+    }
+    fn newline(&mut self) {
+        self.out.push('\n');
+        for _ in 0..self.depth {
+            self.out.push_str(INDENT);
+        }
+    }
+    /// Writes the assigns and comments of a source file or a scope, each on a new line. A
+    /// comment following an assign on its line stays there.
+    fn body(&mut self, node: RawNode<'_>) -> Option<()> {
+        let mut cursor = node.walk();
+        let mut last_row = None;
+        for child in node.named_children(&mut cursor) {
+            let row = child.start_position().row;
+            if child.is_extra() && last_row == Some(row) {
+                self.out.push(' ');
+            } else {
+                if last_row.is_some_and(|x| row > x + 1) {
+                    self.out.push('\n');
+                }
+                self.newline();
+            }
+            if child.is_extra() {
+                self.comments += 1;
+                self.out.push_str(self.slice(child));
+            } else {
+                self.assign(moss::Assign::try_from_raw(child).ok()?)?;
+            }
+            last_row = Some(child.end_position().row);
+        }
+        Some(())
+    }
+    fn assign(&mut self, assign: moss::Assign<'_>) -> Option<()> {
+        self.out.push_str(self.slice(*assign.key().ok()?.raw()));
+        self.out.push_str(" = ");
+        self.value(assign.value().ok()?)?;
+        self.out.push(';');
+        Some(())
+    }
+    fn scope(&mut self, scope: moss::Scope<'_>) -> Option<()> {
+        let raw = *scope.raw();
+        let mut cursor = raw.walk();
+        if raw.named_child_count() == 0 {
+            self.out.push_str("{}");
+            return Some(());
+        }
+        let has_comment = raw.named_children(&mut cursor).any(|x| x.is_extra());
+        if raw.start_position().row == raw.end_position().row && !has_comment {
+            self.out.push('{');
+            for (i, assign) in scope.assigns(&mut cursor).enumerate() {
+                if i > 0 {
+                    self.out.push(' ');
+                }
+                self.assign(assign.ok()?)?;
+            }
+            self.out.push('}');
+            return Some(());
+        }
+        self.out.push('{');
+        self.depth += 1;
+        self.body(raw)?;
+        self.depth -= 1;
+        self.newline();
+        self.out.push('}');
+        Some(())
+    }
+    fn value(&mut self, value: moss::Value<'_>) -> Option<()> {
+        match value.child().ok()? {
+            moss::ValueChild::Call(call) => {
+                self.value(call.func().ok()?)?;
+                self.out.push(' ');
+                self.value(call.param().ok()?)?;
+            }
+            moss::ValueChild::Function(function) => {
+                self.out.push_str(self.slice(*function.param().ok()?.raw()));
+                self.out.push_str(" -> ");
+                self.scope(function.scope().ok()?)?;
+            }
+            moss::ValueChild::Scope(scope) => self.scope(scope)?,
+            moss::ValueChild::Bracket(bracket) => {
+                self.out.push('(');
+                self.value(bracket.value().ok()?)?;
+                self.out.push(')');
+            }
+            moss::ValueChild::FindIn(find) => {
+                self.value(find.value().ok()?)?;
+                self.out.push('.');
+                self.out.push_str(self.slice(*find.name().ok()?.raw()));
+            }
+            moss::ValueChild::MetaFindIn(find) => {
+                self.value(find.value().ok()?)?;
+                self.out.push_str(".@");
+                self.out.push_str(self.slice(*find.name().ok()?.raw()));
+            }
+            moss::ValueChild::MetaFind(find) => {
+                self.out.push('@');
+                self.out.push_str(self.slice(*find.name().ok()?.raw()));
+            }
+            moss::ValueChild::Find(_)
+            | moss::ValueChild::Int(_)
+            | moss::ValueChild::String(_)
+            | moss::ValueChild::Set(_)
+            | moss::ValueChild::Trivial(_) => self.out.push_str(self.slice(*value.raw())),
+        }
+        Some(())
+    }
+}
@@ -9,14 +9,18 @@ use crate::interpreter::expr::Expr;
 use crate::interpreter::expr::HasRef as _;
 use crate::interpreter::file::File;
 use crate::interpreter::file::FileId;
+use crate::interpreter::file::TextEdit;
 use crate::interpreter::module::Module;
 use crate::interpreter::module::ModuleId;
 use crate::interpreter::module::ModuleLocal;
 use crate::interpreter::module::Pools;
 use crate::interpreter::parse::parse_value;
+pub use crate::interpreter::parse::unescape;
 use crate::interpreter::scope::Scope;
 use crate::interpreter::scope::ScopeAuthored;
 use crate::interpreter::scope::ScopeSource;
+use crate::interpreter::source::DiskSource;
+use crate::interpreter::source::SourceProvider;
 use crate::interpreter::thread::Depend;
 use crate::interpreter::thread::Signal;
 use crate::interpreter::thread::Thread;
@@ -35,7 +39,6 @@ use crate::utils::secondary_linked_list::List;
 use crate::utils::unsafe_cell::UnsafeCell;
 use slotmap::SecondaryMap;
 use slotmap::SlotMap;
-use std::collections::HashMap;
 use std::fmt::Debug;
 use std::hash::Hash;
 use std::mem;
@@ -53,6 +56,7 @@ use tokio::task::JoinSet;
 use crate::utils::moss;
 pub use type_sitter::Node;
 use type_sitter::NodeResult;
+use type_sitter::TreeCursor;
 pub use type_sitter::UntypedNode;
 pub type Tree = type_sitter::Tree<moss::SourceFile<'static>>;
 use crate::utils::typed_key::Vec as KeyVec;
@@ -62,8 +66,12 @@ pub mod element;
 pub mod expr;
 pub mod file;
 pub mod function;
+pub mod list;
 pub mod module;
+pub mod query;
 pub mod scope;
+pub mod set;
+pub mod source;
 pub mod thread;
 pub mod value;
 
@@ -184,12 +192,18 @@ pub struct Interpreter {
     pub concurrent: InterpreterConcurrent,
     pub is_concurrent: bool,
     pub builtin_module: Option<ModuleId>,
+    pub source: Box<dyn SourceProvider>,
 }
 
 unsafe impl Sync for Interpreter {}
 
 impl Interpreter {
+    /// Reads sources from disk under `workspace_path`.
     pub fn new(workspace_path: PathBuf) -> Self {
+        let source = Box::new(DiskSource::new(workspace_path.clone()));
+        Self::new_with_source(workspace_path, source)
+    }
+    pub fn new_with_source(workspace_path: PathBuf, source: Box<dyn SourceProvider>) -> Self {
         Self {
             workspace_path: workspace_path,
             strings: StringInterner::new(),
@@ -206,35 +220,27 @@ impl Interpreter {
             },
             is_concurrent: false,
             builtin_module: Default::default(),
+            source,
         }
     }
     pub fn init(&mut self) {
         let module_id = self.add_module(None);
         let scope = erase_mut(unsafe { self.add_scope(None, None, module_id) });
-        let mod_name = self.str2id("mod");
-        let mod_element_id = self
-            .add_element(
-                ElementKey::Name(mod_name),
-                module_id,
-                Some(ElementAuthored::Value(Value::BuiltinFunction(
-                    BuiltinFunction::Mod,
-                ))),
-            )
-            .get_id();
-        let diagnose_name = self.str2id("diagnose");
-        let diagnose_element_id = self
-            .add_element(
-                ElementKey::Name(diagnose_name),
-                module_id,
-                Some(ElementAuthored::Value(Value::BuiltinFunction(
-                    BuiltinFunction::Diagnose,
-                ))),
-            )
-            .get_id();
-        scope.elements = HashMap::from_iter([
-            (mod_name, mod_element_id),
-            (diagnose_name, diagnose_element_id),
-        ]);
+        let builtins = BuiltinFunction::ALL
+            .map(|x| (x.name(), Value::BuiltinFunction(x)))
+            .into_iter()
+            .chain([true, false].map(|x| (value::Bool(x).name(), Value::Bool(value::Bool(x)))));
+        for (name, value) in builtins {
+            let name = self.str2id(name);
+            let element_id = self
+                .add_element(
+                    ElementKey::Name(name),
+                    module_id,
+                    Some(ElementAuthored::Value(value)),
+                )
+                .get_id();
+            scope.elements.insert(name, element_id);
+        }
         self.set_element_value(
             self.get_module(module_id).root_scope.unwrap(),
             Value::Scope(value::Scope(scope.get_id())),
@@ -267,7 +273,7 @@ impl Interpreter {
                 *raw_occupied_entry_mut.get()
             }
             hashbrown::hash_map::RawEntryMut::Vacant(raw_vacant_entry_mut) => {
-                let file = File::new(path.clone(), self);
+                let file = File::new(path.clone(), &*self.source);
                 let file_id = self.files.insert(file);
                 raw_vacant_entry_mut.insert(path.clone(), file_id);
                 file_id
@@ -303,6 +309,147 @@ impl Interpreter {
         module.root_scope = Some(root_scope_element_id);
         id
     }
+    /// Adds every `.moss` file under the workspace's `src/` as a module, unless it is one.
+    pub fn add_src_modules(&mut self) {
+        for path in self.source.list() {
+            if let Some(file_id) = self.find_file(&path)
+                && self.get_file(file_id).is_module.is_some()
+            {
+                continue;
+            }
+            let importers = self.get_importers(&path);
+            self.add_module(Some(path));
+            self.reset_modules(importers);
+        }
+    }
+    /// Re-reads `file_id`, then resets its module and every module depending on it, so the
+    /// next [`Interpreter::run`] resolves only those again.
+    pub fn update_file(&mut self, file_id: FileId) {
+        erase_mut(self).get_file_mut(file_id).update(&*self.source);
+        self.reset_file_modules(file_id);
+    }
+    /// Like [`Interpreter::update_file`], but takes the new text from `edits` instead of disk.
+    pub fn edit_file(&mut self, file_id: FileId, edits: impl IntoIterator<Item = TextEdit>) {
+        self.get_file_mut(file_id).edit(edits);
+        self.reset_file_modules(file_id);
+    }
+    /// Removes `file_id` and its module, then resets the modules importing it, so the next
+    /// [`Interpreter::run`] diagnoses their `mod` of it as not found.
+    pub fn remove_file(&mut self, file_id: FileId) {
+        let file = self.get_file(file_id);
+        let path = file.path.clone();
+        let module_id = file.is_module;
+        let mut modules = self.get_importers(&path);
+        modules.extend(module_id);
+        self.reset_modules(modules);
+        if let Some(module_id) = module_id {
+            self.remove_module(module_id);
+        }
+        self.path2file.remove(&path);
+        self.files.remove(file_id);
+    }
+    /// Drops a module [`Interpreter::reset_modules`] has just reset, which nothing depends on
+    /// anymore.
+    fn remove_module(&mut self, id: ModuleId) {
+        if self.unresolved_modules.contains(id) {
+            self.unresolved_modules.remove(id);
+            self.decrease_workload();
+        }
+        self.modules.remove(id);
+    }
+    fn reset_file_modules(&mut self, file_id: FileId) {
+        let file = self.get_file(file_id);
+        let mut modules = self.get_importers(&file.path);
+        modules.extend(file.is_module);
+        self.reset_modules(modules);
+    }
+    /// Modules that have run `mod` on `path`.
+    fn get_importers(&self, path: &Path) -> Vec<ModuleId> {
+        self.modules
+            .keys()
+            .filter(|id| unsafe { self.get_module_local(*id) }.imports.contains(path))
+            .collect()
+    }
+    /// Resets `modules` and their importers, transitively, to unresolved.
+    fn reset_modules(&mut self, modules: Vec<ModuleId>) {
+        let mut reset = hashbrown::HashSet::new();
+        let mut stack = modules;
+        while let Some(module_id) = stack.pop() {
+            if !reset.insert(module_id) {
+                continue;
+            }
+            let file_id = self.get_module(module_id).file.unwrap();
+            stack.extend(self.get_importers(&self.get_file(file_id).path));
+        }
+        if reset.is_empty() {
+            return;
+        }
+        // kept modules may still list dependants from reset modules on their unresolved elements,
+        // and carry diagnostics reset modules placed on their elements.
+        for (module_id, module) in erase_mut(self).modules.iter_mut() {
+            if reset.contains(&module_id) {
+                continue;
+            }
+            let module_local = module.local.get_mut();
+            module_local
+                .dependants
+                .retain(|x| !reset.contains(&self.get_module_of(*x)));
+            for element in module_local.pools.get_mut::<Element>().iter_mut() {
+                let element_local = element.local.get_mut();
+                element_local
+                    .dependants
+                    .retain(|x| !reset.contains(&self.get_module_of(x.element_id)));
+                element_local
+                    .diagnoistics
+                    .retain(|diagnostic| match diagnostic {
+                        Diagnostic::Custom { by, .. } => !reset.contains(&self.get_module_of(*by)),
+                        _ => true,
+                    });
+            }
+        }
+        for module_id in reset {
+            self.reset_module(module_id);
+        }
+    }
+    fn reset_module(&mut self, id: ModuleId) {
+        let file_id = self.get_module(id).file.unwrap();
+        let authored = ScopeAuthored {
+            source: ScopeSource::File(self.get_file(file_id).tree.root_node().unwrap()),
+            file: file_id,
+        };
+        *self.modules.get_mut(id).unwrap() = Module::new(Some(authored), false, Some(file_id));
+        if !self.unresolved_modules.contains(id) {
+            self.unresolved_modules.push(id);
+            self.increase_workload();
+        }
+        let root_scope_element_id = self.add_element(ElementKey::Temp, id, None).get_id();
+        let module = self.modules.get_mut(id).unwrap();
+        module.root_scope = Some(root_scope_element_id);
+    }
+    /// Visits every scope authored in `file_id`, parents before children.
+    pub fn visit_file_scopes(&self, file_id: FileId, mut visit: impl FnMut(Id<Scope>)) {
+        fn traverse(
+            ip: &Interpreter,
+            file_id: FileId,
+            scope_id: Id<Scope>,
+            visit: &mut impl FnMut(Id<Scope>),
+        ) {
+            visit(scope_id);
+            let scope_local = unsafe { ip.get_local(scope_id) };
+            for child_id in scope_local.children.iter().copied() {
+                if ip.get(child_id).get_file() == Some(file_id) {
+                    traverse(ip, file_id, child_id, visit);
+                }
+            }
+        }
+        let Some(module_id) = self.get_file(file_id).is_module else {
+            return;
+        };
+        let Some(scope_id) = self.get_module_scope(module_id) else {
+            return;
+        };
+        traverse(self, file_id, scope_id, &mut visit);
+    }
     pub async fn run(&mut self) {
         assert!(!self.is_concurrent);
         self.concurrent.module2thread.clear();
@@ -358,9 +505,8 @@ impl Interpreter {
         log::error!("join_all)");
         self.strings.sync_from(&self.concurrent.strings);
         self.is_concurrent = false;
-        erase_mut(self)
-            .unresolved_modules
-            .retain(|key| unsafe { self.get_module_local(key) }.is_resolved());
+        // every listed module has run, one left unresolved waits on a cycle until it is reset.
+        self.unresolved_modules.clear();
         log::error!("run)");
         log::error!("interpreter: {:#?}", self.modules);
     }
@@ -517,6 +663,12 @@ pub trait InterpreterLike: Sized {
         self.get_thread_remote_of_module(self.get_module_of(id))
     }
     fn get_module(&self, id: ModuleId) -> &Module;
+    /// # Returns
+    /// - `None` if the module's root scope is not resolved yet.
+    fn get_module_scope(&self, id: ModuleId) -> Option<Id<Scope>> {
+        let root_scope = self.get_module(id).root_scope?;
+        Some(self.get_element_value(root_scope)?.as_scope().ok()?.0)
+    }
     /// # Safety
     /// - `id` is not remote
     unsafe fn get_module_local(&self, id: ModuleId) -> &ModuleLocal {
@@ -811,15 +963,30 @@ pub trait InterpreterLikeMut: InterpreterLike {
                     unsafe {
                         self.diagnose(
                             Location::Element(element_id),
-                            Diagnostic::RedundantElementKey {},
+                            Diagnostic::RedundantElementKey {
+                                other: redundant_key_element_id,
+                            },
                         );
                         self.diagnose(
                             Location::Element(redundant_key_element_id),
-                            Diagnostic::RedundantElementKey {},
+                            Diagnostic::RedundantElementKey { other: element_id },
                         );
                     };
                 }
             }
+            if let ScopeSource::File(_) = authored.source {
+                if self.get_file(authored.file).read_error.is_some() {
+                    unsafe {
+                        self.diagnose(
+                            Location::Scope(scope_id),
+                            Diagnostic::ReadError {
+                                file: authored.file,
+                            },
+                        )
+                    };
+                }
+                unsafe { self.grammar_error_nodes(scope_id, authored.file) };
+            }
         }
         scope
     }
@@ -893,12 +1060,71 @@ pub trait InterpreterLikeMut: InterpreterLike {
     ) -> Option<T> {
         match result {
             Ok(source) => Some(source),
-            Err(_err) => {
-                unsafe { self.diagnose(location, Diagnostic::GrammarError {}) };
+            Err(err) => {
+                let module_id = match location {
+                    Location::Element(id) => self.get_module_of(id),
+                    Location::Scope(id) => self.get_module_of(id),
+                };
+                let file = self.get_module(module_id).file.unwrap();
+                let module = unsafe { self.get_module_local_mut(module_id) };
+                // already diagnosed when the node was reached before
+                if module.grammar_errors.insert(err.node.raw().id()) {
+                    unsafe {
+                        self.diagnose(
+                            location,
+                            Diagnostic::GrammarError {
+                                source: err.node,
+                                expected: Some(err.kind),
+                                actual: err.node.kind(),
+                                file,
+                            },
+                        )
+                    };
+                }
                 None
             }
         }
     }
+    /// Diagnoses the error and missing nodes of `file` not diagnosed while parsing it,
+    /// like those out of any typed field.
+    /// # Safety
+    /// `scope_id` is local.
+    unsafe fn grammar_error_nodes(&mut self, scope_id: Id<Scope>, file: FileId) {
+        let module_id = self.get(scope_id).module;
+        let mut cursor: TreeCursor<'static> = erase_struct!(self.get_file(file).tree.walk());
+        let mut nodes = vec![];
+        'walk: loop {
+            let node: UntypedNode<'static> = cursor.node();
+            if node.is_error() || node.is_missing() {
+                nodes.push(node);
+            }
+            if !node.is_error() && cursor.goto_first_child() {
+                continue;
+            }
+            while !cursor.goto_next_sibling() {
+                if !cursor.goto_parent() {
+                    break 'walk;
+                }
+            }
+        }
+        for node in nodes {
+            let module = unsafe { self.get_module_local_mut(module_id) };
+            if !module.grammar_errors.insert(node.raw().id()) {
+                continue;
+            }
+            unsafe {
+                self.diagnose(
+                    Location::Scope(scope_id),
+                    Diagnostic::GrammarError {
+                        source: node,
+                        expected: None,
+                        actual: node.kind(),
+                        file,
+                    },
+                )
+            };
+        }
+    }
     /// # Safety
     /// - `module_id` is local.
     unsafe fn run_module(&mut self, module_id: ModuleId) {
@@ -937,6 +1163,14 @@ pub trait InterpreterLikeMut: InterpreterLike {
                         let function = self.get(function.0);
                         self.run_module_element(function.body, module_id);
                     }
+                    Value::List(list) => {
+                        let list = erase(self).get(list.0);
+                        if list.module == module_id {
+                            for element_id in list.elements.iter().copied() {
+                                self.run_module_element(element_id, module_id);
+                            }
+                        }
+                    }
                     _ => (),
                 };
             }
@@ -1,7 +1,15 @@
-use std::fmt::Display;
+use std::{fmt::Display, path::PathBuf};
+
+use serde::Serialize;
+use type_sitter::{Node as _, UntypedNode};
 
 use crate::{
-    interpreter::{InterpreterLike, value::Value},
+    interpreter::{
+        Id, Interpreter, InterpreterLike,
+        element::Element,
+        file::FileId,
+        value::{self, BuiltinFunction, Value},
+    },
     utils::{
         concurrent_string_interner::StringId,
         contexted::{Contexted, WithContext},
@@ -10,31 +18,195 @@ use crate::{
 
 #[derive(Clone, Debug)]
 pub enum Diagnostic {
-    GrammarError {},
-    RedundantElementKey {},
-    FailedFindElement {},
+    /// `expected` is `None` for a error or missing node found out of any typed field.
+    GrammarError {
+        source: UntypedNode<'static>,
+        expected: Option<&'static str>,
+        actual: &'static str,
+        file: FileId,
+    },
+    ReadError {
+        file: FileId,
+    },
+    /// `mod` on `path`, relative to the workspace, with no file there.
+    ModuleNotFound {
+        path: PathBuf,
+    },
+    RedundantElementKey {
+        other: Id<Element>,
+    },
+    /// `is_field` for a name looked up in a target scope, rather than from the scope of the
+    /// find. `candidates` are keys close to `name`, closest first.
+    FailedFindElement {
+        name: StringId,
+        candidates: Vec<StringId>,
+        is_field: bool,
+    },
     FialedFindElementOrPrivateElement {},
-    CanNotFindIn { value: Value },
-    CanNotCallOn { value: Value },
+    CanNotFindIn {
+        value: Value,
+    },
+    CanNotCallOn {
+        value: Value,
+    },
     StringEscapeError {},
-    Custom { text: StringId },
+    /// Int literal out of the range of `i64`.
+    NumberLiteralError {},
+    /// Argument `found` of a builtin where a value of type `expected` is needed.
+    MismatchedType {
+        expected: Value,
+        found: Value,
+    },
+    /// Integer arithmetic of `function` whose result doesn't fit in an `i64`.
+    IntOverflow {
+        function: BuiltinFunction,
+    },
+    DivisionByZero {},
+    /// `int` on a float that is not finite or is out of the range of `i64`.
+    FloatToInt {
+        value: value::Float,
+    },
+    /// `get` on a list at an index it doesn't have.
+    IndexOutOfRange {
+        index: i64,
+        len: usize,
+    },
+    /// `range` over `len` ints, more than the `max` it builds.
+    RangeTooLong {
+        len: u64,
+        max: u64,
+    },
+    Custom {
+        text: StringId,
+        by: Id<Element>,
+    },
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    Error,
+}
+
+impl Display for Severity {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Severity::Error => write!(f, "error"),
+        }
+    }
 }
 
 impl Diagnostic {
     pub fn is_key(&self) -> bool {
         match self {
-            Diagnostic::RedundantElementKey {} => true,
+            Diagnostic::RedundantElementKey { .. } => true,
             _ => false,
         }
     }
+    /// Stable identifier of the variant, kept when its message changes.
+    pub fn code(&self) -> &'static str {
+        match self {
+            Diagnostic::GrammarError { .. } => "grammar-error",
+            Diagnostic::ReadError { .. } => "read-error",
+            Diagnostic::ModuleNotFound { .. } => "module-not-found",
+            Diagnostic::RedundantElementKey { .. } => "redundant-element-key",
+            Diagnostic::FailedFindElement { .. } => "failed-find-element",
+            Diagnostic::FialedFindElementOrPrivateElement {} => "failed-find-element-or-private",
+            Diagnostic::CanNotFindIn { .. } => "can-not-find-in",
+            Diagnostic::CanNotCallOn { .. } => "can-not-call-on",
+            Diagnostic::StringEscapeError {} => "string-escape-error",
+            Diagnostic::NumberLiteralError {} => "number-literal-error",
+            Diagnostic::MismatchedType { .. } => "mismatched-type",
+            Diagnostic::IntOverflow { .. } => "int-overflow",
+            Diagnostic::DivisionByZero {} => "division-by-zero",
+            Diagnostic::FloatToInt { .. } => "float-to-int",
+            Diagnostic::IndexOutOfRange { .. } => "index-out-of-range",
+            Diagnostic::RangeTooLong { .. } => "range-too-long",
+            Diagnostic::Custom { .. } => "custom",
+        }
+    }
+    /// Node the diagnostic is precisely about, overriding the source of where it's reported.
+    pub fn source(&self) -> Option<UntypedNode<'static>> {
+        match self {
+            Diagnostic::GrammarError { source, .. } => Some(*source),
+            _ => None,
+        }
+    }
+    pub fn severity(&self) -> Severity {
+        Severity::Error
+    }
+    /// Elements whose source is related to the diagnostic, with the role they play.
+    pub fn related(&self) -> Vec<(Id<Element>, &'static str)> {
+        match self {
+            Diagnostic::RedundantElementKey { other } => vec![(*other, "other element of the key")],
+            Diagnostic::Custom { by, .. } => vec![(*by, "reported by")],
+            _ => vec![],
+        }
+    }
 }
 
 impl<'a, IP: InterpreterLike> Display for Contexted<'a, Diagnostic, IP> {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self.value {
-            Diagnostic::GrammarError {} => write!(f, "grammar error"),
-            Diagnostic::RedundantElementKey {} => write!(f, "redundant element key"),
-            Diagnostic::FailedFindElement {} => write!(f, "failed find element"),
+            Diagnostic::GrammarError {
+                source,
+                expected,
+                actual,
+                file,
+            } => {
+                if source.is_missing() {
+                    return write!(f, "grammar error: missing {}", actual);
+                }
+                let text = self.ctx.get_source_str(source, *file);
+                let line = text.lines().next().unwrap_or("");
+                let found = if line.chars().count() > 16 || line.len() < text.len() {
+                    format!("{}..", line.chars().take(16).collect::<String>())
+                } else {
+                    line.to_string()
+                };
+                match expected {
+                    Some(expected) => {
+                        write!(f, "grammar error: expected {}, found `{}`", expected, found)
+                    }
+                    None => write!(f, "grammar error: unexpected `{}`", found),
+                }
+            }
+            Diagnostic::ReadError { file } => {
+                let file = self.ctx.get_file(*file);
+                write!(f, "can not read {}", file.path.display())?;
+                if let Some(error) = &file.read_error {
+                    write!(f, ": {}", error)?;
+                }
+                Ok(())
+            }
+            Diagnostic::ModuleNotFound { path } => {
+                write!(f, "module not found: no file {}", path.display())
+            }
+            Diagnostic::RedundantElementKey { .. } => write!(f, "redundant element key"),
+            Diagnostic::FailedFindElement {
+                name,
+                candidates,
+                is_field,
+            } => {
+                if *is_field {
+                    write!(
+                        f,
+                        "no element `{}` in target scope",
+                        &*self.ctx.id2str(*name)
+                    )?;
+                } else {
+                    write!(f, "no element `{}` in scope", &*self.ctx.id2str(*name))?;
+                }
+                let candidates = candidates
+                    .iter()
+                    .map(|x| format!("`{}`", &*self.ctx.id2str(*x)))
+                    .collect::<Vec<_>>();
+                match candidates.as_slice() {
+                    [] => Ok(()),
+                    [candidate] => write!(f, "; did you mean {}?", candidate),
+                    _ => write!(f, "; did you mean one of {}?", candidates.join(", ")),
+                }
+            }
             Diagnostic::FialedFindElementOrPrivateElement {} => {
                 write!(f, "failed find element or private element")
             }
@@ -45,7 +217,176 @@ impl<'a, IP: InterpreterLike> Display for Contexted<'a, Diagnostic, IP> {
                 write!(f, "caan not call on {}", value.with_ctx(self.ctx))
             }
             Diagnostic::StringEscapeError {} => write!(f, "string escape errorr"),
-            Diagnostic::Custom { text } => write!(f, "{}", &*self.ctx.id2str(*text)),
+            Diagnostic::NumberLiteralError {} => write!(f, "invalid number literal"),
+            Diagnostic::MismatchedType { expected, found } => write!(
+                f,
+                "expected {}, found {}",
+                expected.with_ctx(self.ctx),
+                found.with_ctx(self.ctx)
+            ),
+            Diagnostic::IntOverflow { function } => {
+                write!(f, "integer overflow in `{}`", function.name())
+            }
+            Diagnostic::DivisionByZero {} => write!(f, "division by zero"),
+            Diagnostic::FloatToInt { value } => write!(f, "can not convert {} to Int", value),
+            Diagnostic::IndexOutOfRange { index, len } => {
+                write!(
+                    f,
+                    "index {} out of range for a list of length {}",
+                    index, len
+                )
+            }
+            Diagnostic::RangeTooLong { len, max } => {
+                write!(
+                    f,
+                    "range of {} ints is longer than the limit of {}",
+                    len, max
+                )
+            }
+            Diagnostic::Custom { text, .. } => write!(f, "{}", &*self.ctx.id2str(*text)),
+        }
+    }
+}
+
+/// A diagnostic together with the source node it is reported on.
+pub struct Located<'a> {
+    pub source: UntypedNode<'static>,
+    pub diagnostic: &'a Diagnostic,
+    /// Element the diagnostic is reported on, `None` if it is reported on a scope.
+    pub element_id: Option<Id<Element>>,
+}
+
+impl Interpreter {
+    /// Collects the diagnostics of every scope and element authored in `file_id`.
+    pub fn file_diagnostics(&self, file_id: FileId) -> Vec<Located<'_>> {
+        let mut located = vec![];
+        self.visit_file_scopes(file_id, |scope_id| {
+            let scope_local = unsafe { self.get_local(scope_id) };
+            let scope = self.get(scope_id);
+            if let Some(authored) = scope.authored {
+                let source = authored.source.source();
+                for diagnostic in &scope_local.diagnoistics {
+                    let source = diagnostic.source().unwrap_or(source);
+                    located.push(Located {
+                        source,
+                        diagnostic,
+                        element_id: None,
+                    });
+                }
+            }
+            // every element parsed from source, named or not, is in `temp_elements`.
+            for element_id in scope.temp_elements.iter().copied() {
+                let element_local = unsafe { self.get_local(element_id) };
+                let element = self.get(element_id);
+                let Some(source) = &element.source else {
+                    continue;
+                };
+                for diagnostic in &element_local.diagnoistics {
+                    let source = if let Some(source) = diagnostic.source() {
+                        source
+                    } else if diagnostic.is_key() {
+                        source.key_source.unwrap().upcast()
+                    } else {
+                        source.value_source.upcast()
+                    };
+                    located.push(Located {
+                        source,
+                        diagnostic,
+                        element_id: Some(element_id),
+                    });
+                }
+            }
+        });
+        located
+    }
+}
+
+/// Zero-based position, the column counted in bytes.
+#[derive(Clone, Copy, Debug, Serialize)]
+pub struct Position {
+    pub line: usize,
+    pub column: usize,
+}
+
+#[derive(Clone, Copy, Debug, Serialize)]
+pub struct Range {
+    pub start_byte: usize,
+    pub end_byte: usize,
+    pub start: Position,
+    pub end: Position,
+}
+
+impl Range {
+    pub fn of(source: UntypedNode) -> Self {
+        let start = source.start_position();
+        let end = source.end_position();
+        Self {
+            start_byte: source.start_byte(),
+            end_byte: source.end_byte(),
+            start: Position {
+                line: start.row,
+                column: start.column,
+            },
+            end: Position {
+                line: end.row,
+                column: end.column,
+            },
+        }
+    }
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct RelatedRecord {
+    pub file: PathBuf,
+    pub range: Range,
+    pub message: &'static str,
+}
+
+/// Serializable form of a diagnostic, for tools post-processing them.
+#[derive(Clone, Debug, Serialize)]
+pub struct Record {
+    pub code: &'static str,
+    pub severity: Severity,
+    pub message: String,
+    pub file: PathBuf,
+    pub range: Range,
+    pub related: Vec<RelatedRecord>,
+}
+
+impl Interpreter {
+    /// Records of the diagnostics of `file_id`, sorted by position.
+    pub fn file_records(&self, file_id: FileId) -> Vec<Record> {
+        let file = self.get_file(file_id);
+        let mut records = vec![];
+        for located in self.file_diagnostics(file_id) {
+            let related = located
+                .diagnostic
+                .related()
+                .into_iter()
+                .filter_map(|(element_id, message)| {
+                    let source = self.get(element_id).source?;
+                    let file = self.get(source.scope).get_file()?;
+                    let node = source
+                        .key_source
+                        .map(|x| x.upcast())
+                        .unwrap_or(source.value_source.upcast());
+                    Some(RelatedRecord {
+                        file: self.get_file(file).path.clone(),
+                        range: Range::of(node),
+                        message,
+                    })
+                })
+                .collect();
+            records.push(Record {
+                code: located.diagnostic.code(),
+                severity: located.diagnostic.severity(),
+                message: format!("{}", located.diagnostic.with_ctx(self)),
+                file: file.path.clone(),
+                range: Range::of(located.source),
+                related,
+            });
         }
+        records.sort_by_key(|x| x.range.start_byte);
+        records
     }
 }
@@ -1,11 +1,14 @@
-use std::{fs, path::PathBuf};
+use std::{
+    io,
+    path::{Path, PathBuf},
+};
 
 use slotmap::new_key_type;
-use tree_sitter::Parser;
+use tree_sitter::{InputEdit, Parser, Point};
 use type_sitter::Node;
 
 use crate::{
-    interpreter::{InterpreterLike, module::ModuleId},
+    interpreter::{module::ModuleId, source::SourceProvider},
     utils::moss,
 };
 pub type Tree = type_sitter::Tree<moss::SourceFile<'static>>;
@@ -16,13 +19,22 @@ pub struct File {
     pub tree: Tree,
     pub is_module: Option<ModuleId>,
     pub path: PathBuf,
+    /// Why the text couldn't be read, it's empty then.
+    pub read_error: Option<io::Error>,
 }
 
 new_key_type! {pub struct FileId;}
 
+/// A change to the text of a [`File`], replacing `range` or, without one, the whole text.
+/// Points are in rows and byte columns.
+pub struct TextEdit {
+    pub range: Option<(Point, Point)>,
+    pub text: String,
+}
+
 impl File {
-    pub fn new(path: PathBuf, interpreter: &impl InterpreterLike) -> Self {
-        let text = fs::read_to_string(interpreter.get_worksapce_path().join(&path)).unwrap();
+    pub fn new(path: PathBuf, source: &dyn SourceProvider) -> Self {
+        let (text, read_error) = Self::read(&path, source);
         let mut parser = Parser::new();
         parser
             .set_language(&tree_sitter_moss::LANGUAGE.into())
@@ -35,12 +47,77 @@ impl File {
             tree,
             is_module: None,
             path,
+            read_error,
         }
     }
-    pub fn update(&mut self, interpreter: &impl InterpreterLike) {
-        self.text = fs::read_to_string(interpreter.get_worksapce_path().join(&self.path)).unwrap();
+    pub fn update(&mut self, source: &dyn SourceProvider) {
+        (self.text, self.read_error) = Self::read(&self.path, source);
         self.tree = Tree::wrap(self.parser.parse(&self.text, None).unwrap());
         log::error!("sytax:\n{}", self.tree.root_node().to_sexp());
-        self.is_module = None;
+    }
+    fn read(path: &Path, source: &dyn SourceProvider) -> (String, Option<io::Error>) {
+        match source.read(path) {
+            Ok(text) => (text, None),
+            Err(error) => {
+                log::error!("read {}: {}", path.display(), error);
+                (String::new(), Some(error))
+            }
+        }
+    }
+    /// Applies `edits` in order and reparses, reusing the old tree unless the whole text was
+    /// replaced.
+    pub fn edit(&mut self, edits: impl IntoIterator<Item = TextEdit>) {
+        let mut reuse = true;
+        self.read_error = None;
+        for edit in edits {
+            let Some((start, end)) = edit.range else {
+                self.text = edit.text;
+                reuse = false;
+                continue;
+            };
+            let start_byte = self.point2byte(start);
+            let old_end_byte = self.point2byte(end).max(start_byte);
+            let start_position = self.byte2point(start_byte);
+            let old_end_position = self.byte2point(old_end_byte);
+            self.text
+                .replace_range(start_byte..old_end_byte, &edit.text);
+            let new_end_byte = start_byte + edit.text.len();
+            if reuse {
+                self.tree.edit(&InputEdit {
+                    start_byte,
+                    old_end_byte,
+                    new_end_byte,
+                    start_position,
+                    old_end_position,
+                    new_end_position: self.byte2point(new_end_byte),
+                });
+            }
+        }
+        let old_tree = reuse.then(|| self.tree.as_inner());
+        self.tree = Tree::wrap(self.parser.parse(&self.text, old_tree).unwrap());
+        log::error!("sytax:\n{}", self.tree.root_node().to_sexp());
+    }
+    /// Byte offset of `point`, clamped into its row and the text.
+    pub fn point2byte(&self, point: Point) -> usize {
+        let mut row_start = 0;
+        for _ in 0..point.row {
+            match self.text[row_start..].find('\n') {
+                Some(offset) => row_start += offset + 1,
+                None => return self.text.len(),
+            }
+        }
+        let row_end = self.text[row_start..]
+            .find('\n')
+            .map_or(self.text.len(), |offset| row_start + offset);
+        let mut byte = (row_start + point.column).min(row_end);
+        while !self.text.is_char_boundary(byte) {
+            byte -= 1;
+        }
+        byte
+    }
+    pub fn byte2point(&self, byte: usize) -> Point {
+        let before = &self.text[..byte];
+        let row_start = before.rfind('\n').map_or(0, |offset| offset + 1);
+        Point::new(before.matches('\n').count(), byte - row_start)
     }
 }
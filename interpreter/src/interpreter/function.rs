@@ -4,6 +4,7 @@ use crate::{
         Id, Managed,
         element::{Element, ElementKey},
         expr::Expr,
+        list::List,
         module::ModuleId,
         scope::Scope,
         value::{self, Value},
@@ -42,6 +43,17 @@ impl FunctionScope {
     };
 }
 
+#[derive(Debug)]
+pub struct FunctionList {
+    pub elements: Vec<Id<Element>>,
+}
+
+impl FunctionList {
+    pub const DUMMY: Self = Self {
+        elements: Default::default(),
+    };
+}
+
 #[derive(Debug)]
 pub struct FunctionFunction {
     pub body: Id<Element>,
@@ -62,6 +74,7 @@ pub struct FunctionBody {
     pub scopes: KeyVec<Id<Scope>, FunctionScope>,
     pub elements: KeyVec<Id<Element>, FunctionElement>,
     pub functions: KeyVec<Id<Function>, FunctionFunction>,
+    pub lists: KeyVec<Id<List>, FunctionList>,
     pub root_scope: Option<Id<Scope>>,
 }
 
@@ -72,6 +85,7 @@ impl FunctionBody {
             scopes: Default::default(),
             elements: Default::default(),
             functions: Default::default(),
+            lists: Default::default(),
             root_scope: Default::default(),
         }
     }
@@ -0,0 +1,41 @@
+use crate::{
+    interpreter::{Id, Managed, Owner, element::Element, module::ModuleId},
+    utils::unsafe_cell::UnsafeCell,
+};
+
+/// Elements in order, each resolved only once something depends on it. A list only holds
+/// elements of its own module, so it can be run and mapped into a function body like a scope.
+#[derive(Debug)]
+pub struct List {
+    pub elements: Vec<Id<Element>>,
+    pub module: ModuleId,
+}
+
+impl List {
+    pub fn new(elements: Vec<Id<Element>>, module: ModuleId) -> Self {
+        Self { elements, module }
+    }
+}
+
+impl Managed for List {
+    type Local = ();
+
+    type Onwer = Self;
+
+    const NAME: &str = "List";
+
+    fn get_local(&self) -> &UnsafeCell<Self::Local> {
+        unimplemented!()
+    }
+
+    fn get_local_mut(&mut self) -> &mut UnsafeCell<Self::Local> {
+        unimplemented!()
+    }
+
+    fn get_owner(&self) -> Owner<Self::Onwer>
+    where
+        Self: Sized,
+    {
+        Owner::Module(self.module)
+    }
+}
@@ -4,11 +4,13 @@ use crate::{
         Id,
         file::FileId,
         function::{Function, FunctionBody, Param},
+        list::List,
+        set::Set,
     },
     utils::pool::Pool,
 };
 use slotmap::new_key_type;
-use std::{cell::UnsafeCell, fmt::Debug};
+use std::{cell::UnsafeCell, fmt::Debug, path::PathBuf};
 
 use crate::interpreter::{
     element::Element,
@@ -17,7 +19,7 @@ use crate::interpreter::{
 
 gen_pools! {
     #[derive(Debug)]
-    pub Pools{scopes:Scope,elements:Element,functions:Function,params:Param,function_bodies:FunctionBody}
+    pub Pools{scopes:Scope,elements:Element,functions:Function,params:Param,function_bodies:FunctionBody,lists:List,sets:Set}
 }
 
 #[derive(Debug)]
@@ -26,6 +28,10 @@ pub struct ModuleLocal {
     pub authored: Option<ScopeAuthored>,
     pub dependants: Vec<Id<Element>>,
     pub unresolved_count: usize,
+    /// Ids of the nodes already diagnosed as grammar errors.
+    pub grammar_errors: hashbrown::HashSet<usize>,
+    /// Paths of the modules this module has run `mod` on, found or not.
+    pub imports: hashbrown::HashSet<PathBuf>,
 }
 
 pub struct Module {
@@ -57,6 +63,8 @@ impl Module {
                 authored,
                 dependants: Default::default(),
                 unresolved_count: if resolved { 0 } else { 1 },
+                grammar_errors: Default::default(),
+                imports: Default::default(),
             }),
             root_scope: Default::default(),
             file,
@@ -12,6 +12,7 @@ use crate::{
         file::FileId,
         function::{Function, Param},
         scope::{Scope, ScopeAuthored, ScopeSource},
+        set::Set,
         value::{self, Value},
     },
     utils::moss,
@@ -160,6 +161,23 @@ impl<'a, IP: ?Sized + InterpreterLikeMut> Context<'a, IP> {
             meta,
         }))
     }
+    fn parse_int(&mut self, int: moss::Int<'static>) -> Option<Expr> {
+        let value = self
+            .ip
+            .get_source_str(&int, self.file_id)
+            .parse()
+            .ok()
+            .map(|x| Value::Int(value::Int(x)));
+        if value.is_none() {
+            unsafe {
+                self.ip.diagnose(
+                    Location::Element(self.element_id),
+                    Diagnostic::NumberLiteralError {},
+                )
+            };
+        }
+        Some(Expr::Value(value?))
+    }
     fn parse_string(&mut self, string: moss::String<'static>) -> Option<Expr> {
         let mut cursor = erase_struct!(self.ip.get_file(self.file_id).tree.walk());
         let mut value: Option<Cow<str>> = None;
@@ -175,15 +193,10 @@ impl<'a, IP: ?Sized + InterpreterLikeMut> Context<'a, IP> {
                     .grammar_error(Location::Element(self.element_id), content.child())
             }? {
                 moss::StringContentChild::StringEscape(string_escape) => {
-                    match erase(self).ip.get_source_str(&string_escape, self.file_id) {
-                        "\\\"" => Some("\""),
-                        "\\\\" => Some("\\"),
-                        "\\n" => Some("\n"),
-                        "\\t" => Some("\t"),
-                        "\\r" => Some("\r"),
-                        "\\{" => Some("{"),
-                        "\\}" => Some("}"),
-                        _ => {
+                    let escape = erase(self).ip.get_source_str(&string_escape, self.file_id);
+                    match unescape(escape) {
+                        Some(x) => Some(x),
+                        None => {
                             unsafe {
                                 erase_mut(self).ip.diagnose(
                                     Location::Element(self.element_id),
@@ -209,6 +222,24 @@ impl<'a, IP: ?Sized + InterpreterLikeMut> Context<'a, IP> {
                 .str2id(value.as_ref().map(|x| x.as_ref()).unwrap_or("")),
         ))))
     }
+    /// Keys named more than once are kept once.
+    fn parse_set(&mut self, set: moss::Set<'static>) -> Option<Expr> {
+        let mut cursor = erase_struct!(self.ip.get_file(self.file_id).tree.walk());
+        let mut keys = hashbrown::HashSet::new();
+        for key in set.keys(erase_mut(&mut cursor)) {
+            let key = unsafe {
+                erase_mut(self)
+                    .ip
+                    .grammar_error(Location::Element(self.element_id), key)
+            }?;
+            keys.insert(self.ip.get_source_str_id(&key, self.file_id));
+        }
+        let set = unsafe {
+            self.ip
+                .add(Set::new(keys, self.scope.module), self.scope.module)
+        };
+        Some(Expr::Value(Value::Set(value::Set(set.get_id()))))
+    }
     fn parse_function(&mut self, function: moss::Function<'static>) -> Option<Expr> {
         let (param_name, scope) = unsafe {
             let param_name = self
@@ -287,9 +318,7 @@ impl<'a, IP: ?Sized + InterpreterLikeMut> Context<'a, IP> {
     }
     fn parse(&mut self) -> Option<Expr> {
         match self.source_child {
-            moss::ValueChild::Int(int) => Some(Expr::Value(Value::Int(value::Int(
-                self.ip.get_source_str(&int, self.file_id).parse().unwrap(),
-            )))),
+            moss::ValueChild::Int(int) => self.parse_int(int),
             moss::ValueChild::String(string) => self.parse_string(string),
             moss::ValueChild::Call(call) => self.parse_call(call),
             moss::ValueChild::Scope(scope) => self.parse_scope(scope),
@@ -300,6 +329,7 @@ impl<'a, IP: ?Sized + InterpreterLikeMut> Context<'a, IP> {
                 self.parse_find(FindSource::MetaFindIn(find_meta))
             }
             moss::ValueChild::Function(function) => self.parse_function(function),
+            moss::ValueChild::Set(set) => self.parse_set(set),
             moss::ValueChild::Bracket(bracket) => {
                 parse_value(self.ip, bracket.value(), self.element_id, self.scope)
             }
@@ -308,6 +338,20 @@ impl<'a, IP: ?Sized + InterpreterLikeMut> Context<'a, IP> {
     }
 }
 
+/// Character a string escape like `\n` stands for, `None` if it isn't a known escape.
+pub fn unescape(escape: &str) -> Option<&'static str> {
+    match escape {
+        "\\\"" => Some("\""),
+        "\\\\" => Some("\\"),
+        "\\n" => Some("\n"),
+        "\\t" => Some("\t"),
+        "\\r" => Some("\r"),
+        "\\{" => Some("{"),
+        "\\}" => Some("}"),
+        _ => None,
+    }
+}
+
 pub fn parse_value<IP: ?Sized + InterpreterLikeMut>(
     ip: &mut IP,
     source: NodeResult<'static, moss::Value<'static>>,
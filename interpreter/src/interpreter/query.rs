@@ -0,0 +1,374 @@
+use type_sitter::{HasChild as _, Node as _, UntypedNode};
+
+use crate::{
+    interpreter::{
+        Id, Interpreter, InterpreterLike, SRC_PATH,
+        element::Element,
+        expr::Expr,
+        file::FileId,
+        scope::{Scope, ScopeSource},
+    },
+    utils::{concurrent_string_interner::StringId, moss},
+};
+
+/// Most names suggested in place of a name not found.
+pub const MAX_SIMILAR_NAMES: usize = 3;
+
+/// Levenshtein distance between `a` and `b`, counted in chars.
+pub fn edit_distance(a: &str, b: &str) -> usize {
+    let b = b.chars().collect::<Vec<_>>();
+    let mut row = (0..=b.len()).collect::<Vec<_>>();
+    for (i, x) in a.chars().enumerate() {
+        let mut diagonal = row[0];
+        row[0] = i + 1;
+        for (j, y) in b.iter().enumerate() {
+            let above = row[j + 1];
+            row[j + 1] = if x == *y {
+                diagonal
+            } else {
+                1 + diagonal.min(above).min(row[j])
+            };
+            diagonal = above;
+        }
+    }
+    row[b.len()]
+}
+
+/// Up to [`MAX_SIMILAR_NAMES`] keys close enough to `key` to be a typo of it, closest first and
+/// then by name. Looked up like [`InterpreterLike::find_element`]: in `scope_id` and, with
+/// `include_super`, its parents and the builtin scope.
+pub fn find_similar_keys<IP: InterpreterLike>(
+    ip: &IP,
+    scope_id: Id<Scope>,
+    key: StringId,
+    include_super: bool,
+) -> Vec<StringId> {
+    let mut candidates = hashbrown::HashSet::new();
+    let mut scope_iter = Some(scope_id);
+    while let Some(scope_id) = scope_iter {
+        let scope = ip.get::<Scope>(scope_id);
+        candidates.extend(scope.elements.keys().copied());
+        scope_iter = scope.parent.filter(|_| include_super);
+    }
+    if include_super {
+        let builtin_module = ip.get_builtin_module();
+        let scope_id = ip
+            .get_element_value(ip.get_module(builtin_module).root_scope.unwrap())
+            .unwrap()
+            .as_scope()
+            .unwrap()
+            .0;
+        candidates.extend(ip.get::<Scope>(scope_id).elements.keys().copied());
+    }
+    let name = ip.id2str(key).to_string();
+    let max_distance = name.chars().count().div_ceil(3);
+    let mut similar = candidates
+        .into_iter()
+        .filter_map(|candidate| {
+            let candidate_name = ip.id2str(candidate).to_string();
+            let distance = edit_distance(&name, &candidate_name);
+            (distance <= max_distance).then_some((distance, candidate_name, candidate))
+        })
+        .collect::<Vec<_>>();
+    similar.sort_by(|a, b| (a.0, &a.1).cmp(&(b.0, &b.1)));
+    similar
+        .into_iter()
+        .take(MAX_SIMILAR_NAMES)
+        .map(|(.., candidate)| candidate)
+        .collect()
+}
+
+/// Named element in an outline, with the symbols of the scopes authored in its value.
+#[derive(Debug)]
+pub struct Symbol {
+    pub element_id: Id<Element>,
+    pub name: StringId,
+    pub key_source: UntypedNode<'static>,
+    pub children: Vec<Symbol>,
+}
+
+/// Target of a go-to-definition.
+#[derive(Clone, Copy, Debug)]
+pub enum Definition {
+    Element(Id<Element>),
+    File(FileId),
+}
+
+impl Interpreter {
+    /// Elements authored in `file_id` whose key or value source contains `byte`, together with
+    /// that source, innermost first.
+    pub fn get_elements_at(
+        &self,
+        file_id: FileId,
+        byte: usize,
+    ) -> Vec<(Id<Element>, UntypedNode<'static>)> {
+        let mut found = vec![];
+        self.visit_file_scopes(file_id, |scope_id| {
+            for element_id in self.get(scope_id).temp_elements.iter().copied() {
+                let Some(source) = self.get(element_id).source else {
+                    continue;
+                };
+                let sources = source
+                    .key_source
+                    .map(|x| x.upcast())
+                    .into_iter()
+                    .chain([source.value_source.upcast()]);
+                for source in sources {
+                    if source.start_byte() <= byte && byte <= source.end_byte() {
+                        found.push((element_id, source));
+                    }
+                }
+            }
+        });
+        found.sort_by_key(|(_, source)| source.end_byte() - source.start_byte());
+        found
+    }
+    /// Innermost element authored in `file_id` whose key or value source contains `byte`,
+    /// together with that source.
+    pub fn find_element_at(
+        &self,
+        file_id: FileId,
+        byte: usize,
+    ) -> Option<(Id<Element>, UntypedNode<'static>)> {
+        self.get_elements_at(file_id, byte).into_iter().next()
+    }
+    /// Where the name or `mod` import at `byte` is defined: the element a find resolved to,
+    /// or the file of an imported module. A key is its own definition.
+    pub fn find_definition(&self, file_id: FileId, byte: usize) -> Option<Definition> {
+        for (element_id, source) in self.get_elements_at(file_id, byte) {
+            let element = self.get(element_id);
+            if element
+                .source
+                .and_then(|x| x.key_source)
+                .is_some_and(|x| x.upcast() == source)
+            {
+                return Some(Definition::Element(element_id));
+            }
+            if let Some(found_id) = self.get_found_element(element_id) {
+                return Some(Definition::Element(found_id));
+            }
+            if let Some(file_id) = self
+                .get_element_value(element_id)
+                .and_then(|x| x.as_scope().ok())
+                .and_then(|x| self.get_module_file(x.0))
+            {
+                return Some(Definition::File(file_id));
+            }
+        }
+        None
+    }
+    /// File of the module whose root scope is `scope_id`.
+    pub fn get_module_file(&self, scope_id: Id<Scope>) -> Option<FileId> {
+        let scope = self.get(scope_id);
+        match scope.authored?.source {
+            ScopeSource::File(_) => Some(scope.authored?.file),
+            ScopeSource::Scope(_) => None,
+        }
+    }
+    /// File `element_id` is authored in.
+    pub fn get_element_file(&self, element_id: Id<Element>) -> Option<FileId> {
+        self.get(self.get(element_id).source?.scope).get_file()
+    }
+    /// Element a `Find` authored as `element_id` has resolved to. A find never run, like one
+    /// in a function body, is looked up through the scopes it is authored in.
+    pub fn get_found_element(&self, element_id: Id<Element>) -> Option<Id<Element>> {
+        let element_local = unsafe { self.get_local(element_id) };
+        match element_local.expr.as_ref()? {
+            Expr::Ref(r#ref) => Some(r#ref.element_id),
+            Expr::Find(find) => {
+                if let Some(value) = element_local.value {
+                    return Some(value.as_element().ok()?.0);
+                }
+                if find.target.is_some() {
+                    return None;
+                }
+                let scope_id = self.get(element_id).source?.scope;
+                self.find_element(scope_id, find.name, true)
+            }
+            _ => None,
+        }
+    }
+    /// Name node of the find authored as `element_id`, like `name` of `a.name`.
+    pub fn get_find_name_source(&self, element_id: Id<Element>) -> Option<UntypedNode<'static>> {
+        let source = self.get(element_id).source?.value_source;
+        Some(match source.child().ok()? {
+            moss::ValueChild::Find(find) => find.name().ok()?.upcast(),
+            moss::ValueChild::MetaFind(find) => find.name().ok()?.upcast(),
+            moss::ValueChild::FindIn(find) => find.name().ok()?.upcast(),
+            moss::ValueChild::MetaFindIn(find) => find.name().ok()?.upcast(),
+            _ => return None,
+        })
+    }
+    /// Name nodes of every find in any module resolved to `element_id`, with their files.
+    pub fn find_references(&self, element_id: Id<Element>) -> Vec<(FileId, UntypedNode<'static>)> {
+        let mut references = vec![];
+        for (file_id, file) in self.files.iter() {
+            if file.is_module.is_none() {
+                continue;
+            }
+            self.visit_file_scopes(file_id, |scope_id| {
+                for reference_id in self.get(scope_id).temp_elements.iter().copied() {
+                    if self.get_found_element(reference_id) == Some(element_id)
+                        && let Some(source) = self.get_find_name_source(reference_id)
+                    {
+                        references.push((file_id, source));
+                    }
+                }
+            });
+        }
+        references.sort_by_key(|(file_id, source)| {
+            (self.get_file(*file_id).path.clone(), source.start_byte())
+        });
+        references
+    }
+    /// Innermost scope authored in `file_id` whose source contains `byte`.
+    pub fn find_scope_at(&self, file_id: FileId, byte: usize) -> Option<Id<Scope>> {
+        let mut found: Option<(Id<Scope>, usize)> = None;
+        self.visit_file_scopes(file_id, |scope_id| {
+            let source = self.get(scope_id).authored.unwrap().source.source();
+            let len = source.end_byte() - source.start_byte();
+            if source.start_byte() <= byte
+                && byte <= source.end_byte()
+                && found.is_none_or(|(_, x)| len < x)
+            {
+                found = Some((scope_id, len));
+            }
+        });
+        Some(found?.0)
+    }
+    /// Named elements visible from `scope_id`, looked up as `find_element` does: inner scopes
+    /// shadow outer ones and builtins come last.
+    pub fn get_visible_elements(&self, scope_id: Id<Scope>) -> Vec<(StringId, Id<Element>)> {
+        let mut visible = vec![];
+        let mut names = hashbrown::HashSet::new();
+        let mut push = |scope: &Scope| {
+            let mut elements = scope
+                .elements
+                .iter()
+                .filter(|(name, _)| names.insert(**name))
+                .map(|(name, id)| (*name, *id))
+                .collect::<Vec<_>>();
+            elements.sort_by(|a, b| self.id2str(a.0).cmp(&self.id2str(b.0)));
+            visible.extend(elements);
+        };
+        let mut scope_iter = Some(scope_id);
+        while let Some(scope_id) = scope_iter {
+            let scope = self.get(scope_id);
+            push(scope);
+            scope_iter = scope.parent;
+        }
+        if let Some(builtin_module) = self.builtin_module
+            && let Some(scope_id) = self.get_module_scope(builtin_module)
+        {
+            push(self.get(scope_id));
+        }
+        visible
+    }
+    /// Scope found through `path` from `scope_id`, like `a.b` is found in the scope `a`
+    /// resolves to.
+    pub fn find_scope_by_path(&self, scope_id: Id<Scope>, path: &[&str]) -> Option<Id<Scope>> {
+        let mut scope_id = scope_id;
+        for (i, name) in path.iter().enumerate() {
+            let name = self.strings.get(name)?;
+            let element_id = self.find_element(scope_id, name, i == 0)?;
+            scope_id = self.get_element_value(element_id)?.as_scope().ok()?.0;
+        }
+        Some(scope_id)
+    }
+    /// Paths `mod` imports each module by, like `a/b` for `src/a/b.moss`, sorted.
+    pub fn get_module_paths(&self) -> Vec<String> {
+        let mut paths = self
+            .files
+            .values()
+            .filter(|file| file.is_module.is_some())
+            .filter_map(|file| {
+                let path = file.path.strip_prefix(SRC_PATH).ok()?.with_extension("");
+                Some(path.to_string_lossy().replace('\\', "/"))
+            })
+            .collect::<Vec<_>>();
+        paths.sort();
+        paths
+    }
+    /// Outline of `file_id`: the named elements of its root scope, each with the symbols of the
+    /// scopes nested in its value.
+    pub fn get_file_symbols(&self, file_id: FileId) -> Vec<Symbol> {
+        let Some(scope_id) = self
+            .get_file(file_id)
+            .is_module
+            .and_then(|x| self.get_module_scope(x))
+        else {
+            return vec![];
+        };
+        self.get_scope_symbols(file_id, scope_id)
+    }
+    fn get_scope_symbols(&self, file_id: FileId, scope_id: Id<Scope>) -> Vec<Symbol> {
+        let scope = self.get(scope_id);
+        let mut symbols = scope
+            .elements
+            .iter()
+            .filter_map(|(name, element_id)| {
+                Some(Symbol {
+                    element_id: *element_id,
+                    name: *name,
+                    key_source: self.get(*element_id).source?.key_source?.upcast(),
+                    children: vec![],
+                })
+            })
+            .collect::<Vec<_>>();
+        for child_id in unsafe { self.get_local(scope_id) }.children.iter().copied() {
+            if self.get(child_id).get_file() != Some(file_id) {
+                continue;
+            }
+            let child_source = self.get(child_id).authored.unwrap().source.source();
+            let child_symbols = self.get_scope_symbols(file_id, child_id);
+            let parent = symbols.iter_mut().find(|symbol| {
+                let value_source = self.get(symbol.element_id).source.unwrap().value_source;
+                value_source.start_byte() <= child_source.start_byte()
+                    && child_source.end_byte() <= value_source.end_byte()
+            });
+            match parent {
+                Some(parent) => parent.children.extend(child_symbols),
+                None => symbols.extend(child_symbols),
+            }
+        }
+        symbols.sort_by_key(|symbol| symbol.key_source.start_byte());
+        symbols
+    }
+    /// Named elements of every module whose name contains `query`, ignoring case.
+    pub fn find_symbols(&self, query: &str) -> Vec<(FileId, Id<Element>)> {
+        let query = query.to_lowercase();
+        let mut found = vec![];
+        for (file_id, file) in self.files.iter() {
+            if file.is_module.is_none() {
+                continue;
+            }
+            self.visit_file_scopes(file_id, |scope_id| {
+                for (name, element_id) in self.get(scope_id).elements.iter() {
+                    if self.get(*element_id).source.is_some()
+                        && self.id2str(*name).to_lowercase().contains(&query)
+                    {
+                        found.push((file_id, *element_id));
+                    }
+                }
+            });
+        }
+        found
+    }
+    /// Displays where the scope holding `element_id` is authored, like `src/a.moss:3:5`.
+    pub fn display_element_scope(&self, element_id: Id<Element>) -> String {
+        let element = self.get(element_id);
+        if Some(element.module) == self.builtin_module {
+            return "builtin".to_string();
+        }
+        let Some(authored) = element.source.and_then(|x| self.get(x.scope).authored) else {
+            return "unknown".to_string();
+        };
+        let start = authored.source.source().start_position();
+        format!(
+            "{}:{}:{}",
+            self.get_file(authored.file).path.display(),
+            start.row + 1,
+            start.column + 1
+        )
+    }
+}
@@ -7,6 +7,7 @@ use crate::{
         element::Element,
         expr::{self, Expr},
         module::ModuleId,
+        query::find_similar_keys,
         value::{self, Value},
     },
     utils::{erase, erase_mut},
@@ -54,15 +55,12 @@ impl<'a, IP: InterpreterLikeMut> Context<'a, IP> {
     }
     fn run_find(&mut self) -> Option<Value> {
         let find = self.expr.extract_as_find();
-        let scope_id = self.element.source.as_ref().unwrap().scope;
-        let find_element_id = if let Some(target) = find.target {
+        let (scope_id, include_super) = if let Some(target) = find.target {
             let target = self
                 .ip
                 .depend_child_element(self.element.get_id(), target)?;
             match target {
-                Value::Scope(value::Scope(scope_id)) => {
-                    self.ip.find_element(scope_id, find.name, false)
-                }
+                Value::Scope(value::Scope(scope_id)) => (scope_id, false),
                 _ => {
                     unsafe {
                         self.ip.diagnose(
@@ -74,8 +72,9 @@ impl<'a, IP: InterpreterLikeMut> Context<'a, IP> {
                 }
             }
         } else {
-            self.ip.find_element(scope_id, find.name, true)
+            (self.element.source.as_ref().unwrap().scope, true)
         };
+        let find_element_id = self.ip.find_element(scope_id, find.name, include_super);
         if let Some(find_element_id) = find_element_id {
             if !find.meta {
                 *self.expr = Expr::Ref(expr::Ref {
@@ -88,10 +87,15 @@ impl<'a, IP: InterpreterLikeMut> Context<'a, IP> {
                 Some(Value::Element(value::Element(find_element_id)))
             }
         } else {
+            let candidates = find_similar_keys(&*self.ip, scope_id, find.name, include_super);
             unsafe {
                 self.ip.diagnose(
                     Location::Element(self.element.get_id()),
-                    Diagnostic::FailedFindElement {},
+                    Diagnostic::FailedFindElement {
+                        name: find.name,
+                        candidates,
+                        is_field: !include_super,
+                    },
                 )
             };
             return None;
@@ -6,19 +6,49 @@ use crate::{
     interpreter::{
         Id, InterpreterLikeMut, Location, Managed as _, SRC_FILE_EXTENSION, SRC_PATH,
         diagnose::Diagnostic,
-        element::Element,
-        function::{Param, ParamType},
+        element::{Element, ElementAuthored, ElementKey},
+        expr::{self, Expr},
+        function::{Function, Param, ParamType},
+        list::List,
         module::ModuleId,
+        query::find_similar_keys,
+        scope::Scope,
+        set::Set,
         value::{self, BuiltinFunction, Value},
     },
     merge_params,
+    utils::{erase, erase_mut},
 };
 
+const INT_PARAM_TYPE: ParamType = ParamType {
+    depth: 0,
+    value: Value::IntType(value::IntType),
+};
+const FLOAT_PARAM_TYPE: ParamType = ParamType {
+    depth: 0,
+    value: Value::FloatType(value::FloatType),
+};
+const BOOL_PARAM_TYPE: ParamType = ParamType {
+    depth: 0,
+    value: Value::BoolType(value::BoolType),
+};
+const LIST_PARAM_TYPE: ParamType = ParamType {
+    depth: 0,
+    value: Value::ListType(value::ListType),
+};
+const SET_PARAM_TYPE: ParamType = ParamType {
+    depth: 0,
+    value: Value::SetType(value::SetType),
+};
+/// Most ints `range` lists, each an element of its own.
+const MAX_RANGE_LEN: u64 = 1 << 16;
+
 pub struct Context<'a, IP> {
     ip: &'a mut IP,
     element_id: Id<Element>,
     module_id: ModuleId,
     source: Option<UntypedNode<'static>>,
+    expr: &'a mut Expr,
     param: Value,
 }
 
@@ -33,35 +63,269 @@ impl<'a, 'b: 'a, IP: InterpreterLikeMut> Context<'a, IP> {
             element_id: ctx.element.get_id(),
             module_id: ctx.module_id,
             source: ctx.source,
+            expr: &mut *ctx.expr,
             param,
         };
         match builtin_function {
             BuiltinFunction::Mod => ctx.run_mod(),
             BuiltinFunction::Diagnose => ctx.run_diagnose(),
+            BuiltinFunction::Neg => ctx.run_neg(),
+            BuiltinFunction::Add
+            | BuiltinFunction::Sub
+            | BuiltinFunction::Mul
+            | BuiltinFunction::Div
+            | BuiltinFunction::Rem
+            | BuiltinFunction::Eq
+            | BuiltinFunction::Lt
+            | BuiltinFunction::Le => ctx.run_arithmetic(builtin_function),
+            BuiltinFunction::Not => ctx.run_not(),
+            BuiltinFunction::And | BuiltinFunction::Or => ctx.run_bool_binary(builtin_function),
+            BuiltinFunction::If => ctx.run_if(),
+            BuiltinFunction::Int => ctx.run_int(),
+            BuiltinFunction::Float => ctx.run_float(),
+            BuiltinFunction::Len => ctx.run_len(),
+            BuiltinFunction::Get => ctx.run_get(),
+            BuiltinFunction::Concat => ctx.run_concat(),
+            BuiltinFunction::Range => ctx.run_range(),
+            BuiltinFunction::Map => ctx.run_map(),
+            BuiltinFunction::Filter => ctx.run_filter(),
+            BuiltinFunction::Fold => ctx.run_fold(),
+            BuiltinFunction::Has => ctx.run_has(),
+            BuiltinFunction::Union | BuiltinFunction::Intersect => {
+                ctx.run_set_binary(builtin_function)
+            }
+            BuiltinFunction::Select => ctx.run_select(),
+        }
+    }
+    /// Param standing for the result of the call until `function` is called.
+    fn new_param(&mut self, function: Id<Function>, r#type: Option<ParamType>) -> Value {
+        Value::Param(value::Param(
+            unsafe {
+                self.ip.add(
+                    Param {
+                        function,
+                        element: self.element_id,
+                        r#type,
+                    },
+                    self.module_id,
+                )
+            }
+            .get_id(),
+        ))
+    }
+    /// Value of the element `name` of the scope passed as argument, like `a` of
+    /// `add {a = 1; b = 2;}`.
+    fn depend_field(&mut self, scope_id: Id<Scope>, name: &str) -> Option<Value> {
+        let element_id = self.find_field(scope_id, name)?;
+        self.ip
+            .depend_element(self.element_id, element_id, self.source)
+    }
+    /// Element `name` of the scope passed as argument, for a builtin passing it on unresolved.
+    fn find_field(&mut self, scope_id: Id<Scope>, name: &str) -> Option<Id<Element>> {
+        let name = self.ip.str2id(name);
+        let Some(element_id) = self.ip.find_element(scope_id, name, false) else {
+            let candidates = find_similar_keys(&*self.ip, scope_id, name, false);
+            unsafe {
+                self.ip.diagnose(
+                    Location::Element(self.element_id),
+                    Diagnostic::FailedFindElement {
+                        name,
+                        candidates,
+                        is_field: true,
+                    },
+                )
+            };
+            return None;
+        };
+        Some(element_id)
+    }
+    fn mismatched_type(&mut self, expected: Value, found: Value) {
+        // already diagnosed where it failed
+        if let Value::Error(_) = found {
+            return;
+        }
+        unsafe {
+            self.ip.diagnose(
+                Location::Element(self.element_id),
+                Diagnostic::MismatchedType { expected, found },
+            )
+        };
+    }
+    fn expect_int(&mut self, value: Value) -> Option<i64> {
+        match value {
+            Value::Int(value::Int(value)) => Some(value),
+            found => {
+                self.mismatched_type(Value::IntType(value::IntType), found);
+                None
+            }
+        }
+    }
+    fn expect_bool(&mut self, value: Value) -> Option<bool> {
+        match value {
+            Value::Bool(value::Bool(value)) => Some(value),
+            found => {
+                self.mismatched_type(Value::BoolType(value::BoolType), found);
+                None
+            }
         }
     }
+    fn expect_list(&mut self, value: Value) -> Option<Id<List>> {
+        match value {
+            Value::List(value::List(value)) => Some(value),
+            found => {
+                self.mismatched_type(Value::ListType(value::ListType), found);
+                None
+            }
+        }
+    }
+    fn expect_set(&mut self, value: Value) -> Option<Id<Set>> {
+        match value {
+            Value::Set(value::Set(value)) => Some(value),
+            found => {
+                self.mismatched_type(Value::SetType(value::SetType), found);
+                None
+            }
+        }
+    }
+    fn expect_scope(&mut self, value: Value) -> Option<Id<Scope>> {
+        match value {
+            Value::Scope(value::Scope(value)) => Some(value),
+            found => {
+                self.mismatched_type(Value::ScopeType(value::ScopeType), found);
+                None
+            }
+        }
+    }
+    fn expect_function(&mut self, value: Value) -> Option<()> {
+        match value {
+            Value::Function(_) | Value::BuiltinFunction(_) => Some(()),
+            found => {
+                self.mismatched_type(Value::FunctionType(value::FunctionType), found);
+                None
+            }
+        }
+    }
+    fn add_temp_element(&mut self, authored: ElementAuthored) -> Id<Element> {
+        self.ip
+            .add_element(ElementKey::Temp, self.module_id, Some(authored))
+            .get_id()
+    }
+    /// New list of `elements`. One of another module is held through a local ref, as a list
+    /// only holds elements of its own module.
+    fn add_list(&mut self, elements: Vec<Id<Element>>) -> Id<List> {
+        let elements = elements
+            .into_iter()
+            .map(|element_id| {
+                if self.ip.get(element_id).module == self.module_id {
+                    element_id
+                } else {
+                    self.add_temp_element(ElementAuthored::Expr(Expr::Ref(expr::Ref {
+                        element_id,
+                    })))
+                }
+            })
+            .collect();
+        unsafe {
+            self.ip
+                .add(List::new(elements, self.module_id), self.module_id)
+        }
+        .get_id()
+    }
+    /// Element of a scope holding `fields`, the argument of a call a builtin builds.
+    fn add_scope_element(&mut self, fields: &[(&str, Id<Element>)]) -> Id<Element> {
+        let scope = unsafe { erase_mut(self).ip.add_scope(None, None, self.module_id) };
+        for (name, element_id) in fields.iter().copied() {
+            scope.elements.insert(self.ip.str2id(name), element_id);
+        }
+        let scope_id = scope.get_id();
+        self.add_temp_element(ElementAuthored::Value(Value::Scope(value::Scope(scope_id))))
+    }
+    fn add_call_element(&mut self, function: Id<Element>, param: Id<Element>) -> Id<Element> {
+        self.add_temp_element(ElementAuthored::Expr(Expr::Call(expr::Call {
+            function,
+            param,
+        })))
+    }
+    fn add_builtin_call_element(
+        &mut self,
+        builtin_function: BuiltinFunction,
+        fields: &[(&str, Id<Element>)],
+    ) -> Id<Element> {
+        let function = self.add_temp_element(ElementAuthored::Value(Value::BuiltinFunction(
+            builtin_function,
+        )));
+        let param = self.add_scope_element(fields);
+        self.add_call_element(function, param)
+    }
+    /// Element applying the function `function_id` resolved to `function` to `param`: the
+    /// result of a builtin, or the `value` of the scope a Moss function gives.
+    fn add_apply_element(
+        &mut self,
+        function_id: Id<Element>,
+        function: Value,
+        param: Id<Element>,
+    ) -> Id<Element> {
+        let call = self.add_call_element(function_id, param);
+        let Value::Function(_) = function else {
+            return call;
+        };
+        let name = self.ip.str2id("value");
+        self.add_temp_element(ElementAuthored::Expr(Expr::Find(expr::Find {
+            target: Some(call),
+            name,
+            meta: false,
+        })))
+    }
+    /// Value of the element a builtin has built its result as. The call becomes a ref to it so
+    /// that running it again doesn't build it again.
+    fn depend_built(&mut self, element_id: Id<Element>) -> Option<Value> {
+        *self.expr = Expr::Ref(expr::Ref { element_id });
+        self.ip
+            .depend_element(self.element_id, element_id, self.source)
+    }
+    /// List and function fields of the argument of `map`, `filter` and `fold`, or `Err` with
+    /// the value of the call while either is a param.
+    fn depend_list_function(
+        &mut self,
+        scope_id: Id<Scope>,
+        r#type: Option<ParamType>,
+    ) -> Option<Result<(Id<List>, Id<Element>, Value), Value>> {
+        let list = self.depend_field(scope_id, "list")?;
+        let function_id = self.find_field(scope_id, "function")?;
+        let function = self
+            .ip
+            .depend_element(self.element_id, function_id, self.source)?;
+        if let Some(param_function) = merge_params!(self.ip, list, function) {
+            return Some(Err(self.new_param(param_function, r#type)));
+        }
+        let list = self.expect_list(list)?;
+        self.expect_function(function)?;
+        Some(Ok((list, function_id, function)))
+    }
     fn run_mod(&mut self) -> Option<Value> {
         if let Some(function) = merge_params!(self.ip, self.param) {
-            return Some(Value::Param(value::Param(
-                unsafe {
-                    self.ip.add(
-                        Param {
-                            function,
-                            element: self.element_id,
-                            r#type: None,
-                        },
-                        self.module_id,
-                    )
-                }
-                .get_id(),
-            )));
+            return Some(self.new_param(function, None));
         }
         let path = self.param.as_string().ok()?.0;
         let path = Path::new(SRC_PATH)
             .join(&*self.ip.id2str(path))
             .with_extension(SRC_FILE_EXTENSION);
-        let file = self.ip.find_file(path)?;
-        let module_id = self.ip.get_file(file).is_module?;
+        unsafe { self.ip.get_module_local_mut(self.module_id) }
+            .imports
+            .insert(path.clone());
+        let Some(module_id) = self
+            .ip
+            .find_file(&path)
+            .and_then(|x| self.ip.get_file(x).is_module)
+        else {
+            unsafe {
+                self.ip.diagnose(
+                    Location::Element(self.element_id),
+                    Diagnostic::ModuleNotFound { path },
+                )
+            };
+            return None;
+        };
         let module = self.ip.get_module(module_id);
         let root_scope = self
             .ip
@@ -74,53 +338,506 @@ impl<'a, 'b: 'a, IP: InterpreterLikeMut> Context<'a, IP> {
     }
     fn run_diagnose(&mut self) -> Option<Value> {
         let scope = self.param.as_scope().ok()?.0;
-        let on_key = self.ip.str2id("on");
-        let source_key = self.ip.str2id("source");
-        let text_key = self.ip.str2id("text");
-
-        let on = self.ip.depend_element(
-            self.element_id,
-            self.ip.find_element(scope, on_key, false)?,
-            self.source,
-        )?;
-        let text = self.ip.depend_element(
-            self.element_id,
-            self.ip.find_element(scope, text_key, false)?,
-            self.source,
-        )?;
-        let source_element = self.ip.depend_element(
-            self.element_id,
-            self.ip.find_element(scope, source_key, false)?,
-            self.source,
-        )?;
+        let on = self.depend_field(scope, "on")?;
+        let text = self.depend_field(scope, "text")?;
+        let source_element = self.depend_field(scope, "source")?;
         if let Some(function) = merge_params!(self.ip, on, text, source_element) {
-            return Some(Value::Param(value::Param(unsafe {
-                self.ip
-                    .add(
-                        Param {
-                            function,
-                            element: self.element_id,
-                            r#type: Some(ParamType {
-                                depth: 0,
-                                value: Value::Trivial(value::Trivial),
-                            }),
-                        },
-                        self.module_id,
-                    )
-                    .get_id()
-            })));
+            return Some(self.new_param(
+                function,
+                Some(ParamType {
+                    depth: 0,
+                    value: Value::Trivial(value::Trivial),
+                }),
+            ));
         }
-        let on = on.as_int().ok()?.0;
+        let on = self.expect_bool(on)?;
         let source_element = source_element.as_element().ok()?.0;
         let text = text.as_string().ok()?.0;
-        if on != 0 && self.ip.is_local(source_element) {
+        if on && self.ip.is_local(source_element) {
             unsafe {
                 self.ip.diagnose(
                     Location::Element(source_element),
-                    Diagnostic::Custom { text },
+                    Diagnostic::Custom {
+                        text,
+                        by: self.element_id,
+                    },
                 )
             };
         }
         Some(Value::Trivial(value::Trivial))
     }
+    /// `neg x`, the negation of the int or float `x`.
+    fn run_neg(&mut self) -> Option<Value> {
+        if let Some(function) = merge_params!(self.ip, self.param) {
+            return Some(self.new_param(function, None));
+        }
+        match self.param {
+            Value::Int(value::Int(value)) => match value.checked_neg() {
+                Some(value) => Some(Value::Int(value::Int(value))),
+                None => {
+                    unsafe {
+                        self.ip.diagnose(
+                            Location::Element(self.element_id),
+                            Diagnostic::IntOverflow {
+                                function: BuiltinFunction::Neg,
+                            },
+                        )
+                    };
+                    None
+                }
+            },
+            Value::Float(value) => Some(Value::Float(value::Float::new(-value.get()))),
+            found => {
+                self.mismatched_type(Value::IntType(value::IntType), found);
+                None
+            }
+        }
+    }
+    /// `f {a = x; b = y;}` on `x` and `y`, both ints or both floats. Floats follow IEEE rules,
+    /// where dividing by zero gives an infinity or NaN and NaN equals nothing.
+    fn run_arithmetic(&mut self, builtin_function: BuiltinFunction) -> Option<Value> {
+        let is_comparison = matches!(
+            builtin_function,
+            BuiltinFunction::Eq | BuiltinFunction::Lt | BuiltinFunction::Le
+        );
+        if let Some(function) = merge_params!(self.ip, self.param) {
+            let param_type = is_comparison.then_some(BOOL_PARAM_TYPE);
+            return Some(self.new_param(function, param_type));
+        }
+        let scope = self.param.as_scope().ok()?.0;
+        let a = self.depend_field(scope, "a")?;
+        let b = self.depend_field(scope, "b")?;
+        if let Some(function) = merge_params!(self.ip, a, b) {
+            let param_type = if is_comparison {
+                Some(BOOL_PARAM_TYPE)
+            } else {
+                // the type of the other operand, if it isn't a param too
+                [a, b]
+                    .into_iter()
+                    .find_map(|x| x.get_type())
+                    .map(|value| ParamType { depth: 0, value })
+            };
+            return Some(self.new_param(function, param_type));
+        }
+        let (a, b) = match (a, b) {
+            (Value::Int(a), Value::Int(b)) => {
+                return self.run_int_arithmetic(builtin_function, a.0, b.0);
+            }
+            (Value::Float(a), Value::Float(b)) => (a.get(), b.get()),
+            (Value::Float(_), found) => {
+                self.mismatched_type(Value::FloatType(value::FloatType), found);
+                return None;
+            }
+            (Value::Int(_), found) | (found, _) => {
+                self.mismatched_type(Value::IntType(value::IntType), found);
+                return None;
+            }
+        };
+        let result = match builtin_function {
+            BuiltinFunction::Add => a + b,
+            BuiltinFunction::Sub => a - b,
+            BuiltinFunction::Mul => a * b,
+            BuiltinFunction::Div => a / b,
+            BuiltinFunction::Rem => a % b,
+            BuiltinFunction::Eq => return Some(Value::Bool(value::Bool(a == b))),
+            BuiltinFunction::Lt => return Some(Value::Bool(value::Bool(a < b))),
+            BuiltinFunction::Le => return Some(Value::Bool(value::Bool(a <= b))),
+            _ => unreachable!(),
+        };
+        Some(Value::Float(value::Float::new(result)))
+    }
+    /// Division truncates toward zero and the remainder takes the sign of `a`.
+    fn run_int_arithmetic(
+        &mut self,
+        builtin_function: BuiltinFunction,
+        a: i64,
+        b: i64,
+    ) -> Option<Value> {
+        let result = match builtin_function {
+            BuiltinFunction::Add => a.checked_add(b),
+            BuiltinFunction::Sub => a.checked_sub(b),
+            BuiltinFunction::Mul => a.checked_mul(b),
+            BuiltinFunction::Div | BuiltinFunction::Rem if b == 0 => {
+                unsafe {
+                    self.ip.diagnose(
+                        Location::Element(self.element_id),
+                        Diagnostic::DivisionByZero {},
+                    )
+                };
+                return None;
+            }
+            BuiltinFunction::Div => a.checked_div(b),
+            BuiltinFunction::Rem => a.checked_rem(b),
+            BuiltinFunction::Eq => return Some(Value::Bool(value::Bool(a == b))),
+            BuiltinFunction::Lt => return Some(Value::Bool(value::Bool(a < b))),
+            BuiltinFunction::Le => return Some(Value::Bool(value::Bool(a <= b))),
+            _ => unreachable!(),
+        };
+        match result {
+            Some(value) => Some(Value::Int(value::Int(value))),
+            None => {
+                unsafe {
+                    self.ip.diagnose(
+                        Location::Element(self.element_id),
+                        Diagnostic::IntOverflow {
+                            function: builtin_function,
+                        },
+                    )
+                };
+                None
+            }
+        }
+    }
+    /// `int x`, the float `x` truncated toward zero.
+    fn run_int(&mut self) -> Option<Value> {
+        if let Some(function) = merge_params!(self.ip, self.param) {
+            return Some(self.new_param(function, Some(INT_PARAM_TYPE)));
+        }
+        let value = match self.param {
+            Value::Float(value) => value,
+            found => {
+                self.mismatched_type(Value::FloatType(value::FloatType), found);
+                return None;
+            }
+        };
+        let truncated = value.get().trunc();
+        // `i64::MAX as f64` is 2^63, one past the largest `i64`.
+        if (i64::MIN as f64..i64::MAX as f64).contains(&truncated) {
+            return Some(Value::Int(value::Int(truncated as i64)));
+        }
+        unsafe {
+            self.ip.diagnose(
+                Location::Element(self.element_id),
+                Diagnostic::FloatToInt { value },
+            )
+        };
+        None
+    }
+    /// `float x`, the float nearest to the int `x`.
+    fn run_float(&mut self) -> Option<Value> {
+        if let Some(function) = merge_params!(self.ip, self.param) {
+            return Some(self.new_param(function, Some(FLOAT_PARAM_TYPE)));
+        }
+        let value = self.expect_int(self.param)?;
+        Some(Value::Float(value::Float::new(value as f64)))
+    }
+    /// `not x`, the negation of the bool `x`.
+    fn run_not(&mut self) -> Option<Value> {
+        if let Some(function) = merge_params!(self.ip, self.param) {
+            return Some(self.new_param(function, Some(BOOL_PARAM_TYPE)));
+        }
+        let value = self.expect_bool(self.param)?;
+        Some(Value::Bool(value::Bool(!value)))
+    }
+    /// `and {a = x; b = y;}` or `or {a = x; b = y;}` on the bools `x` and `y`. `b` isn't
+    /// depended on if `a` alone decides the result.
+    fn run_bool_binary(&mut self, builtin_function: BuiltinFunction) -> Option<Value> {
+        if let Some(function) = merge_params!(self.ip, self.param) {
+            return Some(self.new_param(function, Some(BOOL_PARAM_TYPE)));
+        }
+        let scope = self.param.as_scope().ok()?.0;
+        let a = self.depend_field(scope, "a")?;
+        let decided = match builtin_function {
+            BuiltinFunction::And => false,
+            BuiltinFunction::Or => true,
+            _ => unreachable!(),
+        };
+        if !matches!(a, Value::Param(_)) {
+            let a = self.expect_bool(a)?;
+            if a == decided {
+                return Some(Value::Bool(value::Bool(a)));
+            }
+        }
+        let b = self.depend_field(scope, "b")?;
+        if let Some(function) = merge_params!(self.ip, a, b) {
+            return Some(self.new_param(function, Some(BOOL_PARAM_TYPE)));
+        }
+        let b = self.expect_bool(b)?;
+        Some(Value::Bool(value::Bool(b)))
+    }
+    /// `if {cond = x; then = y; else = z;}`, `y` if the bool `x` is true and `z` otherwise.
+    /// Only the branch taken is depended on, so the other one is never resolved or diagnosed.
+    /// A result depending on a param has both resolved, as each call of the function runs the
+    /// whole `if` again.
+    fn run_if(&mut self) -> Option<Value> {
+        if let Some(function) = merge_params!(self.ip, self.param) {
+            return Some(self.new_param(function, None));
+        }
+        let scope = self.param.as_scope().ok()?.0;
+        let cond = self.depend_field(scope, "cond")?;
+        let is_then = match cond {
+            Value::Param(_) => true,
+            cond => self.expect_bool(cond)?,
+        };
+        let (taken, other) = if is_then {
+            ("then", "else")
+        } else {
+            ("else", "then")
+        };
+        let value = self.depend_field(scope, taken)?;
+        if merge_params!(self.ip, cond, value).is_none() {
+            return Some(value);
+        }
+        let other = self.depend_field(scope, other)?;
+        let function = merge_params!(self.ip, cond, value, other).unwrap();
+        Some(self.new_param(function, None))
+    }
+    fn run_len(&mut self) -> Option<Value> {
+        if let Some(function) = merge_params!(self.ip, self.param) {
+            return Some(self.new_param(function, Some(INT_PARAM_TYPE)));
+        }
+        let list = self.expect_list(self.param)?;
+        Some(Value::Int(value::Int(
+            self.ip.get(list).elements.len() as i64
+        )))
+    }
+    /// Only the element at `index` is depended on.
+    fn run_get(&mut self) -> Option<Value> {
+        if let Some(function) = merge_params!(self.ip, self.param) {
+            return Some(self.new_param(function, None));
+        }
+        let scope = self.param.as_scope().ok()?.0;
+        let list = self.depend_field(scope, "list")?;
+        let index = self.depend_field(scope, "index")?;
+        if let Some(function) = merge_params!(self.ip, list, index) {
+            return Some(self.new_param(function, None));
+        }
+        let list = self.expect_list(list)?;
+        let index = self.expect_int(index)?;
+        let elements = &erase(self.ip).get(list).elements;
+        let Some(element_id) = usize::try_from(index)
+            .ok()
+            .and_then(|x| elements.get(x))
+            .copied()
+        else {
+            unsafe {
+                self.ip.diagnose(
+                    Location::Element(self.element_id),
+                    Diagnostic::IndexOutOfRange {
+                        index,
+                        len: elements.len(),
+                    },
+                )
+            };
+            return None;
+        };
+        let value = self
+            .ip
+            .depend_element(self.element_id, element_id, self.source)?;
+        if let Some(function) = merge_params!(self.ip, value) {
+            return Some(self.new_param(function, None));
+        }
+        Some(value)
+    }
+    fn run_concat(&mut self) -> Option<Value> {
+        if let Some(function) = merge_params!(self.ip, self.param) {
+            return Some(self.new_param(function, Some(LIST_PARAM_TYPE)));
+        }
+        let scope = self.param.as_scope().ok()?.0;
+        let a = self.depend_field(scope, "a")?;
+        let b = self.depend_field(scope, "b")?;
+        if let Some(function) = merge_params!(self.ip, a, b) {
+            return Some(self.new_param(function, Some(LIST_PARAM_TYPE)));
+        }
+        let a = self.expect_list(a)?;
+        let b = self.expect_list(b)?;
+        let elements = [a, b]
+            .into_iter()
+            .flat_map(|x| erase(self.ip).get(x).elements.iter().copied())
+            .collect();
+        Some(Value::List(value::List(self.add_list(elements))))
+    }
+    fn run_range(&mut self) -> Option<Value> {
+        if let Some(function) = merge_params!(self.ip, self.param) {
+            return Some(self.new_param(function, Some(LIST_PARAM_TYPE)));
+        }
+        let scope = self.param.as_scope().ok()?.0;
+        let from = self.depend_field(scope, "from")?;
+        let to = self.depend_field(scope, "to")?;
+        if let Some(function) = merge_params!(self.ip, from, to) {
+            return Some(self.new_param(function, Some(LIST_PARAM_TYPE)));
+        }
+        let from = self.expect_int(from)?;
+        let to = self.expect_int(to)?;
+        let len = if from < to { to.abs_diff(from) } else { 0 };
+        if len > MAX_RANGE_LEN {
+            unsafe {
+                self.ip.diagnose(
+                    Location::Element(self.element_id),
+                    Diagnostic::RangeTooLong {
+                        len,
+                        max: MAX_RANGE_LEN,
+                    },
+                )
+            };
+            return None;
+        }
+        let elements = (from..to)
+            .map(|x| self.add_temp_element(ElementAuthored::Value(Value::Int(value::Int(x)))))
+            .collect();
+        Some(Value::List(value::List(self.add_list(elements))))
+    }
+    /// Each application is run only once something depends on its element.
+    fn run_map(&mut self) -> Option<Value> {
+        if let Some(function) = merge_params!(self.ip, self.param) {
+            return Some(self.new_param(function, Some(LIST_PARAM_TYPE)));
+        }
+        let scope = self.param.as_scope().ok()?.0;
+        let (list, function_id, function) =
+            match self.depend_list_function(scope, Some(LIST_PARAM_TYPE))? {
+                Ok(fields) => fields,
+                Err(param) => return Some(param),
+            };
+        let elements = erase(self.ip)
+            .get(list)
+            .elements
+            .iter()
+            .map(|x| self.add_apply_element(function_id, function, *x))
+            .collect();
+        Some(Value::List(value::List(self.add_list(elements))))
+    }
+    /// Built as a chain of `if`s, the `n`th giving the kept elements among the first `n`.
+    fn run_filter(&mut self) -> Option<Value> {
+        if let Some(function) = merge_params!(self.ip, self.param) {
+            return Some(self.new_param(function, Some(LIST_PARAM_TYPE)));
+        }
+        let scope = self.param.as_scope().ok()?.0;
+        let (list, function_id, function) =
+            match self.depend_list_function(scope, Some(LIST_PARAM_TYPE))? {
+                Ok(fields) => fields,
+                Err(param) => return Some(param),
+            };
+        let empty = self.add_list(vec![]);
+        let mut kept =
+            self.add_temp_element(ElementAuthored::Value(Value::List(value::List(empty))));
+        for element_id in erase(self.ip).get(list).elements.iter().copied() {
+            let cond = self.add_apply_element(function_id, function, element_id);
+            let single = self.add_list(vec![element_id]);
+            let single =
+                self.add_temp_element(ElementAuthored::Value(Value::List(value::List(single))));
+            let then = self
+                .add_builtin_call_element(BuiltinFunction::Concat, &[("a", kept), ("b", single)]);
+            kept = self.add_builtin_call_element(
+                BuiltinFunction::If,
+                &[("cond", cond), ("then", then), ("else", kept)],
+            );
+        }
+        self.depend_built(kept)
+    }
+    /// Built as a chain of applications, each passed the one before as `a`.
+    fn run_fold(&mut self) -> Option<Value> {
+        if let Some(function) = merge_params!(self.ip, self.param) {
+            return Some(self.new_param(function, None));
+        }
+        let scope = self.param.as_scope().ok()?.0;
+        let (list, function_id, function) = match self.depend_list_function(scope, None)? {
+            Ok(fields) => fields,
+            Err(param) => return Some(param),
+        };
+        let mut acc = self.find_field(scope, "init")?;
+        for element_id in erase(self.ip).get(list).elements.iter().copied() {
+            let param = self.add_scope_element(&[("a", acc), ("b", element_id)]);
+            acc = self.add_apply_element(function_id, function, param);
+        }
+        self.depend_built(acc)
+    }
+    fn run_has(&mut self) -> Option<Value> {
+        if let Some(function) = merge_params!(self.ip, self.param) {
+            return Some(self.new_param(function, Some(BOOL_PARAM_TYPE)));
+        }
+        let scope = self.param.as_scope().ok()?.0;
+        let set = self.depend_field(scope, "set")?;
+        let key = self.depend_field(scope, "key")?;
+        if let Some(function) = merge_params!(self.ip, set, key) {
+            return Some(self.new_param(function, Some(BOOL_PARAM_TYPE)));
+        }
+        let set = self.expect_set(set)?;
+        let key = match key {
+            Value::String(value::String(key)) => key,
+            found => {
+                self.mismatched_type(Value::StringType(value::StringType), found);
+                return None;
+            }
+        };
+        Some(Value::Bool(value::Bool(
+            self.ip.get(set).keys.contains(&key),
+        )))
+    }
+    fn run_set_binary(&mut self, builtin_function: BuiltinFunction) -> Option<Value> {
+        if let Some(function) = merge_params!(self.ip, self.param) {
+            return Some(self.new_param(function, Some(SET_PARAM_TYPE)));
+        }
+        let scope = self.param.as_scope().ok()?.0;
+        let a = self.depend_field(scope, "a")?;
+        let b = self.depend_field(scope, "b")?;
+        if let Some(function) = merge_params!(self.ip, a, b) {
+            return Some(self.new_param(function, Some(SET_PARAM_TYPE)));
+        }
+        let a = self.expect_set(a)?;
+        let b = self.expect_set(b)?;
+        let (a, b) = (&erase(self.ip).get(a).keys, &erase(self.ip).get(b).keys);
+        let keys = match builtin_function {
+            BuiltinFunction::Union => a.union(b).copied().collect(),
+            BuiltinFunction::Intersect => a.intersection(b).copied().collect(),
+            _ => unreachable!(),
+        };
+        let set = unsafe { self.ip.add(Set::new(keys, self.module_id), self.module_id) };
+        Some(Value::Set(value::Set(set.get_id())))
+    }
+    /// The scope only has the elements of `scope` named in `set`, each also missing from
+    /// `scope` being diagnosed.
+    fn run_select(&mut self) -> Option<Value> {
+        if let Some(function) = merge_params!(self.ip, self.param) {
+            return Some(self.new_param(function, None));
+        }
+        let scope = self.param.as_scope().ok()?.0;
+        let selected = self.depend_field(scope, "scope")?;
+        let set = self.depend_field(scope, "set")?;
+        if let Some(function) = merge_params!(self.ip, selected, set) {
+            return Some(self.new_param(function, None));
+        }
+        let selected = self.expect_scope(selected)?;
+        let set = self.expect_set(set)?;
+        let mut keys = erase(self.ip)
+            .get(set)
+            .keys
+            .iter()
+            .copied()
+            .collect::<Vec<_>>();
+        keys.sort_by(|a, b| self.ip.id2str(*a).cmp(&self.ip.id2str(*b)));
+        let mut elements = vec![];
+        for key in keys {
+            // only the scope's own elements, not the builtins `find_element` falls back to.
+            match self.ip.get(selected).elements.get(&key).copied() {
+                Some(element_id) => elements.push((key, element_id)),
+                None => {
+                    let candidates = find_similar_keys(&*self.ip, selected, key, false);
+                    unsafe {
+                        self.ip.diagnose(
+                            Location::Element(self.element_id),
+                            Diagnostic::FailedFindElement {
+                                name: key,
+                                candidates,
+                                is_field: true,
+                            },
+                        )
+                    };
+                }
+            }
+        }
+        if elements.len() < erase(self.ip).get(set).keys.len() {
+            return None;
+        }
+        let projected = unsafe { erase_mut(self).ip.add_scope(None, None, self.module_id) };
+        for (key, element_id) in elements {
+            // a scope only holds elements of its own module, like a list
+            let element_id = if self.ip.get(element_id).module == self.module_id {
+                element_id
+            } else {
+                self.add_temp_element(ElementAuthored::Expr(Expr::Ref(expr::Ref { element_id })))
+            };
+            projected.elements.insert(key, element_id);
+        }
+        Some(Value::Scope(value::Scope(projected.get_id())))
+    }
 }
@@ -9,8 +9,9 @@ use crate::{
         expr::{self, Expr, HasRef as _},
         function::{
             Function, FunctionBody, FunctionElement, FunctionElementAuthored, FunctionFunction,
-            FunctionScope,
+            FunctionList, FunctionScope,
         },
+        list::List,
         module::ModuleId,
         scope::Scope,
         value::{self, Value},
@@ -25,6 +26,7 @@ pub struct CallContext<'a, IP> {
     module_id: ModuleId,
     element_map: Vec<Option<Id<Element>>>,
     scope_map: Vec<Option<Id<Scope>>>,
+    list_map: Vec<Option<Id<List>>>,
     param: Id<Element>,
 }
 
@@ -50,6 +52,7 @@ impl<'a, IP: InterpreterLikeMut> CallContext<'a, IP> {
             module_id: ctx.module_id,
             element_map: Default::default(),
             scope_map: Default::default(),
+            list_map: Default::default(),
             param,
         };
         Some(Value::Scope(value::Scope(
@@ -104,6 +107,7 @@ impl<'a, IP: InterpreterLikeMut> CallContext<'a, IP> {
                     Value::Element(value::Element(id)) => {
                         Value::Element(value::Element(self.run_element(id)))
                     }
+                    Value::List(value::List(id)) => Value::List(value::List(self.run_list(id))),
                     _ => *value,
                 };
                 ElementAuthored::Value(value)
@@ -123,6 +127,28 @@ impl<'a, IP: InterpreterLikeMut> CallContext<'a, IP> {
         self.element_map[id.to_idx()] = Some(mapped_id);
         mapped_id
     }
+    fn run_list(&mut self, id: Id<List>) -> Id<List> {
+        if let Some(id) = self.list_map.get(id.to_idx()).copied().flatten() {
+            return id;
+        }
+        let list = erase(self).body.lists.get(id);
+        let elements = list
+            .elements
+            .iter()
+            .map(|element_id| self.run_element(*element_id))
+            .collect();
+        let mapped_id = unsafe {
+            erase_mut(self)
+                .ip
+                .add(List::new(elements, self.module_id), self.module_id)
+        }
+        .get_id();
+        if self.list_map.len() <= id.to_idx() {
+            self.list_map.resize(id.to_idx() + 1, Default::default());
+        }
+        self.list_map[id.to_idx()] = Some(mapped_id);
+        mapped_id
+    }
     fn run_function(&mut self, id: Id<Function>) -> Id<Function> {
         let function = erase(self).body.functions.get(id);
         let mapped_funcion = unsafe {
@@ -163,9 +189,21 @@ impl<'a, 'b: 'a, IP: InterpreterLikeMut> BodyDependContext<'a, IP> {
         match value {
             Value::Scope(value::Scope(scope_id)) => self.depend_scope(scope_id),
             Value::Function(value::Function(id)) => self.depend_function(id),
+            Value::List(value::List(id)) => self.depend_list(id),
             _ => Some(()),
         }
     }
+    /// Lists of other modules are left out, as they are never mapped into the body.
+    fn depend_list(&mut self, list_id: Id<List>) -> Option<()> {
+        let list = erase(self).ip.get(list_id);
+        if list.module != self.ip.get(self.element_id).module {
+            return Some(());
+        }
+        for element_id in list.elements.iter().copied() {
+            self.depend_element(element_id)?
+        }
+        Some(())
+    }
     fn depend_function(&mut self, function_id: Id<Function>) -> Option<()> {
         let function = self.ip.get(function_id);
         self.depend_element(function.body)
@@ -179,6 +217,7 @@ pub struct BodyContext<'a, IP: InterpreterLikeMut> {
     body: &'a mut FunctionBody,
     element_map: HashMap<Id<Element>, Id<Element>>,
     scope_map: HashMap<Id<Scope>, Id<Scope>>,
+    list_map: HashMap<Id<List>, Id<List>>,
 }
 
 impl<'a, 'b: 'a, IP: InterpreterLikeMut> BodyContext<'a, IP> {
@@ -202,6 +241,7 @@ impl<'a, 'b: 'a, IP: InterpreterLikeMut> BodyContext<'a, IP> {
             body,
             element_map: Default::default(),
             scope_map: Default::default(),
+            list_map: Default::default(),
         };
         ctx.body.root_scope = Some(ctx.map_scope(function.scope));
         Some(Value::FunctionBody(value::FunctionBody(ctx.body.get_id())))
@@ -273,6 +313,12 @@ impl<'a, 'b: 'a, IP: InterpreterLikeMut> BodyContext<'a, IP> {
                         let id = self.map_function(id);
                         FunctionElementAuthored::Value(Value::Function(value::Function(id)))
                     }
+                    Value::List(value::List(id))
+                        if self.ip.get(id).module == self.function.module =>
+                    {
+                        let id = self.map_list(id);
+                        FunctionElementAuthored::Value(Value::List(value::List(id)))
+                    }
                     Value::Element(value::Element(id)) => {
                         let element = unsafe { self.ip.get_local(id) };
                         let id = if let Value::Param(param) = element.value.unwrap() {
@@ -294,6 +340,21 @@ impl<'a, 'b: 'a, IP: InterpreterLikeMut> BodyContext<'a, IP> {
 
         mapped_id
     }
+    fn map_list(&mut self, list_id: Id<List>) -> Id<List> {
+        if let Some(mapped) = self.list_map.get(&list_id).copied() {
+            return mapped;
+        }
+        let mapped_id = self.body.lists.insert(FunctionList::DUMMY);
+        self.list_map.insert(list_id, mapped_id);
+        let list = erase(self).ip.get(list_id);
+        let elements = list
+            .elements
+            .iter()
+            .map(|element_id| self.map_element(*element_id))
+            .collect();
+        *self.body.lists.get_mut(mapped_id) = FunctionList { elements };
+        mapped_id
+    }
     fn map_function(&mut self, function_id: Id<Function>) -> Id<Function> {
         let function = erase(self).ip.get(function_id);
         let mut mapped_function = FunctionFunction::new(function.body);
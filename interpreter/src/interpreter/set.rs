@@ -0,0 +1,41 @@
+use crate::{
+    interpreter::{Managed, Owner, module::ModuleId},
+    utils::{concurrent_string_interner::StringId, unsafe_cell::UnsafeCell},
+};
+
+/// Keys, like the names of the elements of a scope, without their values. Set builtins make
+/// new sets and never change one.
+#[derive(Debug)]
+pub struct Set {
+    pub keys: hashbrown::HashSet<StringId>,
+    pub module: ModuleId,
+}
+
+impl Set {
+    pub fn new(keys: hashbrown::HashSet<StringId>, module: ModuleId) -> Self {
+        Self { keys, module }
+    }
+}
+
+impl Managed for Set {
+    type Local = ();
+
+    type Onwer = Self;
+
+    const NAME: &str = "Set";
+
+    fn get_local(&self) -> &UnsafeCell<Self::Local> {
+        unimplemented!()
+    }
+
+    fn get_local_mut(&mut self) -> &mut UnsafeCell<Self::Local> {
+        unimplemented!()
+    }
+
+    fn get_owner(&self) -> Owner<Self::Onwer>
+    where
+        Self: Sized,
+    {
+        Owner::Module(self.module)
+    }
+}
@@ -0,0 +1,125 @@
+use std::{
+    collections::HashMap,
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+use walkdir::WalkDir;
+
+use crate::interpreter::{SRC_FILE_EXTENSION, SRC_PATH};
+
+/// Where the interpreter reads source files from. Paths are relative to the workspace.
+pub trait SourceProvider: Send + Sync {
+    fn read(&self, path: &Path) -> io::Result<String>;
+    /// Every source file under `src/`, sorted.
+    fn list(&self) -> Vec<PathBuf>;
+}
+
+fn is_src_file(path: &Path) -> bool {
+    path.starts_with(SRC_PATH)
+        && path
+            .extension()
+            .is_some_and(|extension| extension == SRC_FILE_EXTENSION)
+}
+
+pub struct DiskSource {
+    pub workspace_path: PathBuf,
+}
+
+impl DiskSource {
+    pub fn new(workspace_path: PathBuf) -> Self {
+        Self { workspace_path }
+    }
+}
+
+impl SourceProvider for DiskSource {
+    fn read(&self, path: &Path) -> io::Result<String> {
+        fs::read_to_string(self.workspace_path.join(path))
+    }
+    fn list(&self) -> Vec<PathBuf> {
+        let mut paths = WalkDir::new(self.workspace_path.join(SRC_PATH))
+            .into_iter()
+            .filter_map(Result::ok)
+            .filter(|entry| entry.file_type().is_file())
+            .filter_map(|entry| {
+                Some(
+                    entry
+                        .path()
+                        .strip_prefix(&self.workspace_path)
+                        .ok()?
+                        .to_path_buf(),
+                )
+            })
+            .filter(|path| is_src_file(path))
+            .collect::<Vec<_>>();
+        paths.sort();
+        paths
+    }
+}
+
+/// Files held in memory only, like test fixtures.
+#[derive(Default)]
+pub struct MemorySource {
+    pub files: HashMap<PathBuf, String>,
+}
+
+impl MemorySource {
+    pub fn new(files: impl IntoIterator<Item = (impl Into<PathBuf>, impl Into<String>)>) -> Self {
+        Self {
+            files: files
+                .into_iter()
+                .map(|(path, text)| (path.into(), text.into()))
+                .collect(),
+        }
+    }
+}
+
+impl SourceProvider for MemorySource {
+    fn read(&self, path: &Path) -> io::Result<String> {
+        self.files
+            .get(path)
+            .cloned()
+            .ok_or_else(|| io::ErrorKind::NotFound.into())
+    }
+    fn list(&self) -> Vec<PathBuf> {
+        let mut paths = self
+            .files
+            .keys()
+            .filter(|path| is_src_file(path))
+            .cloned()
+            .collect::<Vec<_>>();
+        paths.sort();
+        paths
+    }
+}
+
+/// Buffers, like unsaved editor ones, shadowing the files of `base`.
+pub struct OverlaySource<B> {
+    pub buffers: MemorySource,
+    pub base: B,
+}
+
+impl<B: SourceProvider> OverlaySource<B> {
+    pub fn new(base: B) -> Self {
+        Self {
+            buffers: Default::default(),
+            base,
+        }
+    }
+}
+
+impl<B: SourceProvider> SourceProvider for OverlaySource<B> {
+    fn read(&self, path: &Path) -> io::Result<String> {
+        match self.buffers.files.get(path) {
+            Some(text) => Ok(text.clone()),
+            None => self.base.read(path),
+        }
+    }
+    fn list(&self) -> Vec<PathBuf> {
+        let mut paths = self.base.list();
+        paths.extend(self.buffers.list());
+        paths.sort();
+        paths.dedup();
+        paths
+    }
+}
@@ -4,8 +4,9 @@ use crate::{
     interpreter::{
         Id,
         element::{self, ElementKey},
-        function,
+        function, list,
         scope::{self},
+        set,
     },
     utils::contexted::{Contexted, WithContext},
 };
@@ -20,16 +21,103 @@ use crate::{interpreter::InterpreterLike, utils::concurrent_string_interner::Str
 pub enum BuiltinFunction {
     Mod,
     Diagnose,
+    Add,
+    Sub,
+    Mul,
+    Div,
+    Rem,
+    Neg,
+    Eq,
+    Lt,
+    Le,
+    And,
+    Or,
+    Not,
+    If,
+    Int,
+    Float,
+    Len,
+    Get,
+    Concat,
+    Range,
+    Map,
+    Filter,
+    Fold,
+    Has,
+    Union,
+    Intersect,
+    Select,
 }
-impl fmt::Display for BuiltinFunction {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(f, "~")?;
+impl BuiltinFunction {
+    /// Every builtin, each put in the builtin scope under its [`name`](Self::name).
+    pub const ALL: [BuiltinFunction; 28] = [
+        BuiltinFunction::Mod,
+        BuiltinFunction::Diagnose,
+        BuiltinFunction::Add,
+        BuiltinFunction::Sub,
+        BuiltinFunction::Mul,
+        BuiltinFunction::Div,
+        BuiltinFunction::Rem,
+        BuiltinFunction::Neg,
+        BuiltinFunction::Eq,
+        BuiltinFunction::Lt,
+        BuiltinFunction::Le,
+        BuiltinFunction::And,
+        BuiltinFunction::Or,
+        BuiltinFunction::Not,
+        BuiltinFunction::If,
+        BuiltinFunction::Int,
+        BuiltinFunction::Float,
+        BuiltinFunction::Len,
+        BuiltinFunction::Get,
+        BuiltinFunction::Concat,
+        BuiltinFunction::Range,
+        BuiltinFunction::Map,
+        BuiltinFunction::Filter,
+        BuiltinFunction::Fold,
+        BuiltinFunction::Has,
+        BuiltinFunction::Union,
+        BuiltinFunction::Intersect,
+        BuiltinFunction::Select,
+    ];
+    pub fn name(&self) -> &'static str {
         match self {
-            BuiltinFunction::Mod => write!(f, "mod"),
-            BuiltinFunction::Diagnose => write!(f, "diagnose"),
+            BuiltinFunction::Mod => "mod",
+            BuiltinFunction::Diagnose => "diagnose",
+            BuiltinFunction::Add => "add",
+            BuiltinFunction::Sub => "sub",
+            BuiltinFunction::Mul => "mul",
+            BuiltinFunction::Div => "div",
+            BuiltinFunction::Rem => "rem",
+            BuiltinFunction::Neg => "neg",
+            BuiltinFunction::Eq => "eq",
+            BuiltinFunction::Lt => "lt",
+            BuiltinFunction::Le => "le",
+            BuiltinFunction::And => "and",
+            BuiltinFunction::Or => "or",
+            BuiltinFunction::Not => "not",
+            BuiltinFunction::If => "if",
+            BuiltinFunction::Int => "int",
+            BuiltinFunction::Float => "float",
+            BuiltinFunction::Len => "len",
+            BuiltinFunction::Get => "get",
+            BuiltinFunction::Concat => "concat",
+            BuiltinFunction::Range => "range",
+            BuiltinFunction::Map => "map",
+            BuiltinFunction::Filter => "filter",
+            BuiltinFunction::Fold => "fold",
+            BuiltinFunction::Has => "has",
+            BuiltinFunction::Union => "union",
+            BuiltinFunction::Intersect => "intersect",
+            BuiltinFunction::Select => "select",
         }
     }
 }
+impl fmt::Display for BuiltinFunction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "~{}", self.name())
+    }
+}
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub struct Int(pub i64);
@@ -46,6 +134,54 @@ impl Display for IntType {
         write!(f, "Int")
     }
 }
+/// IEEE double, kept as its bits so that [`Value`] is `Eq`. Every NaN is stored as
+/// [`f64::NAN`], so two floats are equal exactly when they have the same bits: a NaN equals
+/// itself and `0.0` differs from `-0.0`. Builtins compare by IEEE rules instead.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Float(u64);
+impl Float {
+    pub fn new(value: f64) -> Self {
+        Self(if value.is_nan() { f64::NAN } else { value }.to_bits())
+    }
+    pub fn get(&self) -> f64 {
+        f64::from_bits(self.0)
+    }
+}
+/// Shortest text parsing back to the same finite float, like `1.0` or `1e-7`, which always has a
+/// `.` or an exponent so that it reads apart from an int. Infinities and NaN show as `inf`,
+/// `-inf` and `NaN`, which aren't literals.
+impl Display for Float {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{:?}", self.get())
+    }
+}
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FloatType;
+impl Display for FloatType {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "Float")
+    }
+}
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Bool(pub bool);
+impl Bool {
+    /// Names the builtin scope holds each bool under.
+    pub fn name(&self) -> &'static str {
+        if self.0 { "true" } else { "false" }
+    }
+}
+impl Display for Bool {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BoolType;
+impl Display for BoolType {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "Bool")
+    }
+}
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub struct String(pub StringId);
 impl<'a, Ctx: ?Sized + InterpreterLike> Display for Contexted<'a, String, Ctx> {
@@ -65,9 +201,15 @@ pub struct Scope(pub Id<scope::Scope>);
 impl<'a, Ctx: ?Sized + InterpreterLike> Display for Contexted<'a, Scope, Ctx> {
     fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
         let scope = self.ctx.get(self.value.0);
+        let mut keys = scope
+            .elements
+            .keys()
+            .map(|key| self.ctx.id2str(*key))
+            .collect::<Vec<_>>();
+        keys.sort_by(|a, b| a.deref().cmp(b.deref()));
         write!(f, "{{")?;
-        for key in scope.elements.keys() {
-            write!(f, "{}, ", self.ctx.id2str(*key).deref(),)?;
+        for key in keys {
+            write!(f, "{}, ", key.deref())?;
         }
         write!(f, "}}")
     }
@@ -80,6 +222,60 @@ impl Display for ScopeType {
     }
 }
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct List(pub Id<list::List>);
+impl<'a, Ctx: ?Sized + InterpreterLike> Display for Contexted<'a, List, Ctx> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let list = self.ctx.get(self.value.0);
+        write!(f, "[")?;
+        for (i, element_id) in list.elements.iter().copied().enumerate() {
+            if i > 0 {
+                write!(f, ", ")?;
+            }
+            match self.ctx.get(element_id).value.get() {
+                Some(value) => write!(f, "{}", value.with_ctx(self.ctx))?,
+                None => write!(f, "..")?,
+            }
+        }
+        write!(f, "]")
+    }
+}
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ListType;
+impl Display for ListType {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "List")
+    }
+}
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Set(pub Id<set::Set>);
+/// Sorted keys marked with `#`, like `#{x, y}`, so that a set doesn't read as a scope.
+impl<'a, Ctx: ?Sized + InterpreterLike> Display for Contexted<'a, Set, Ctx> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let set = self.ctx.get(self.value.0);
+        let mut keys = set
+            .keys
+            .iter()
+            .map(|key| self.ctx.id2str(*key))
+            .collect::<Vec<_>>();
+        keys.sort_by(|a, b| a.deref().cmp(b.deref()));
+        write!(f, "#{{")?;
+        for (i, key) in keys.iter().enumerate() {
+            if i > 0 {
+                write!(f, ", ")?;
+            }
+            write!(f, "{}", key.deref())?;
+        }
+        write!(f, "}}")
+    }
+}
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SetType;
+impl Display for SetType {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "Set")
+    }
+}
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub struct Element(pub Id<element::Element>);
 impl<'a, Ctx: ?Sized + InterpreterLike> Display for Contexted<'a, Element, Ctx> {
     fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
@@ -166,10 +362,18 @@ impl<'a, Ctx: ?Sized + InterpreterLike> Display for Contexted<'a, Param, Ctx> {
 pub enum Value {
     Int(Int),
     IntType(IntType),
+    Float(Float),
+    FloatType(FloatType),
+    Bool(Bool),
+    BoolType(BoolType),
     String(String),
     StringType(StringType),
     Scope(Scope),
     ScopeType(ScopeType),
+    List(List),
+    ListType(ListType),
+    Set(Set),
+    SetType(SetType),
     Element(Element),
     ElementType(ElementType),
     Function(Function),
@@ -183,6 +387,33 @@ pub enum Value {
 }
 
 impl Value {
+    /// Type of the value, `None` if it's not known yet or has none.
+    pub fn get_type(&self) -> Option<Value> {
+        Some(match self {
+            Value::Int(_) => Value::IntType(IntType),
+            Value::Float(_) => Value::FloatType(FloatType),
+            Value::Bool(_) => Value::BoolType(BoolType),
+            Value::String(_) => Value::StringType(StringType),
+            Value::Scope(_) => Value::ScopeType(ScopeType),
+            Value::List(_) => Value::ListType(ListType),
+            Value::Set(_) => Value::SetType(SetType),
+            Value::Element(_) => Value::ElementType(ElementType),
+            Value::Function(_) | Value::FunctionBody(_) | Value::BuiltinFunction(_) => {
+                Value::FunctionType(FunctionType)
+            }
+            Value::IntType(_)
+            | Value::FloatType(_)
+            | Value::BoolType(_)
+            | Value::StringType(_)
+            | Value::ScopeType(_)
+            | Value::ListType(_)
+            | Value::SetType(_)
+            | Value::ElementType(_)
+            | Value::FunctionType(_)
+            | Value::TypeType(_) => Value::TypeType(TypeType),
+            Value::Error(_) | Value::Trivial(_) | Value::Param(_) => return None,
+        })
+    }
     pub fn merge_param(
         self,
         ctx: &(impl InterpreterLike + ?Sized),
@@ -218,10 +449,18 @@ impl<'a, Ctx: InterpreterLike + ?Sized> Display for Contexted<'a, Value, Ctx> {
         match *self.value {
             Value::Int(value) => write!(f, "{}", value),
             Value::IntType(value) => write!(f, "{}", value),
+            Value::Float(value) => write!(f, "{}", value),
+            Value::FloatType(value) => write!(f, "{}", value),
+            Value::Bool(value) => write!(f, "{}", value),
+            Value::BoolType(value) => write!(f, "{}", value),
             Value::String(value) => write!(f, "{}", value.with_ctx(self.ctx)),
             Value::StringType(value) => write!(f, "{}", value),
             Value::Scope(value) => write!(f, "{}", value.with_ctx(self.ctx)),
             Value::ScopeType(value) => write!(f, "{}", value),
+            Value::List(value) => write!(f, "{}", value.with_ctx(self.ctx)),
+            Value::ListType(value) => write!(f, "{}", value),
+            Value::Set(value) => write!(f, "{}", value.with_ctx(self.ctx)),
+            Value::SetType(value) => write!(f, "{}", value),
             Value::Element(value) => write!(f, "{}", value.with_ctx(self.ctx)),
             Value::ElementType(value) => write!(f, "{}", value),
             Value::Function(value) => write!(f, "{}", value),
@@ -7,3 +7,5 @@ pub mod type_sitter_lang;
 pub mod utils;
 
 pub mod interpreter;
+
+pub mod format;
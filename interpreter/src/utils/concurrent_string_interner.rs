@@ -67,6 +67,16 @@ impl Interner {
         };
         StringId(id)
     }
+    /// Id of `s` if it has been interned.
+    pub fn get(&self, s: &str) -> Option<StringId> {
+        let mut hasher = self.hash_builder.build_hasher();
+        s.hash(&mut hasher);
+        let hash = hasher.finish();
+        self.map
+            .raw_entry()
+            .from_hash(hash, |id| s == self.id2strings[*id])
+            .map(|(id, ())| StringId(*id))
+    }
     pub fn resolve(&self, id: StringId) -> impl Deref<Target = str> {
         self.id2strings[id.0].as_str()
     }
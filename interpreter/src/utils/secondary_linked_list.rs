@@ -37,6 +37,9 @@ impl<K: Copy + Key> List<K> {
         }
         self.secondary.remove(key);
     }
+    pub fn contains(&self, key: K) -> bool {
+        self.secondary.contains_key(key)
+    }
     pub fn len(&self) -> usize {
         self.secondary.len()
     }
@@ -0,0 +1,44 @@
+//! Formatting Moss source.
+
+use std::{fs, path::Path};
+
+use moss_interpreter::format::format;
+use walkdir::WalkDir;
+
+#[test]
+fn normalizes_spacing_and_indentation() {
+    let text = "a=1 ;\nf = param->{b=param;\nc = { d = b ; } ;};\n\n\n\nvalue=( f 1 ).c.d;";
+    let expected = "\
+a = 1;
+f = param -> {
+    b = param;
+    c = {d = b;};
+};
+
+value = (f 1).c.d;
+";
+    assert_eq!(format(text).unwrap(), expected);
+    assert_eq!(format(expected).unwrap(), expected);
+}
+
+#[test]
+fn syntax_error_is_not_formatted() {
+    assert_eq!(format("a = ;\n"), None);
+}
+
+#[test]
+fn language_examples_are_formatted() {
+    let root = Path::new(env!("CARGO_MANIFEST_DIR")).join("../language_example");
+    for entry in WalkDir::new(root).into_iter().filter_map(Result::ok) {
+        if entry.path().extension().is_none_or(|x| x != "moss") {
+            continue;
+        }
+        let text = fs::read_to_string(entry.path()).unwrap();
+        assert_eq!(
+            format(&text).as_deref(),
+            Some(text.as_str()),
+            "{}",
+            entry.path().display()
+        );
+    }
+}
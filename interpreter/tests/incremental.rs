@@ -0,0 +1,153 @@
+//! Re-running a workspace after one of its files changes.
+
+use std::{env, fs, path::PathBuf, process};
+
+use moss_interpreter::{
+    interpreter::{Interpreter, InterpreterLike as _, InterpreterLikeMut as _, SRC_PATH},
+    utils::contexted::WithContext as _,
+};
+
+fn workspace(name: &str, files: &[(&str, &str)]) -> PathBuf {
+    let workspace = env::temp_dir().join(format!("moss-{}-{}", name, process::id()));
+    let _ = fs::remove_dir_all(&workspace);
+    fs::create_dir_all(workspace.join(SRC_PATH)).unwrap();
+    for (path, text) in files {
+        fs::write(workspace.join(SRC_PATH).join(path), text).unwrap();
+    }
+    workspace
+}
+
+/// Displays the value of `name` in the root scope of the module at `path`.
+fn value_of(interpreter: &mut Interpreter, path: &str, name: &str) -> String {
+    let name = interpreter.str2id(name);
+    let file_id = interpreter
+        .find_file(PathBuf::from(SRC_PATH).join(path))
+        .unwrap();
+    let module_id = interpreter.get_file(file_id).is_module.unwrap();
+    let scope_id = interpreter.get_module_scope(module_id).unwrap();
+    let element_id = interpreter.get(scope_id).elements[&name];
+    let value = interpreter.get_element_value(element_id).unwrap();
+    format!("{}", value.with_ctx(interpreter))
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn update_file_reruns_dependants_only() {
+    let workspace = workspace(
+        "update-file",
+        &[
+            ("a.moss", "b = mod \"b\";\nvalue = b.value;\n"),
+            ("b.moss", "value = 1;\n"),
+            ("c.moss", "value = 3;\n"),
+        ],
+    );
+    let mut interpreter = Interpreter::new(workspace.clone());
+    interpreter.init();
+    interpreter.add_src_modules();
+    interpreter.run().await;
+    assert_eq!(value_of(&mut interpreter, "a.moss", "value"), "1");
+
+    let c_file = interpreter
+        .find_file(PathBuf::from(SRC_PATH).join("c.moss"))
+        .unwrap();
+    let c_module = interpreter.get_file(c_file).is_module.unwrap();
+    let c_scope = interpreter.get_module_scope(c_module).unwrap();
+
+    fs::write(workspace.join(SRC_PATH).join("b.moss"), "value = 2;\n").unwrap();
+    let b_file = interpreter
+        .find_file(PathBuf::from(SRC_PATH).join("b.moss"))
+        .unwrap();
+    interpreter.update_file(b_file);
+    interpreter.run().await;
+
+    assert_eq!(value_of(&mut interpreter, "b.moss", "value"), "2");
+    assert_eq!(value_of(&mut interpreter, "a.moss", "value"), "2");
+    assert_eq!(interpreter.get_module_scope(c_module), Some(c_scope));
+    assert_eq!(value_of(&mut interpreter, "c.moss", "value"), "3");
+
+    fs::remove_dir_all(workspace).unwrap();
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn added_module_reruns_its_importers() {
+    let workspace = workspace(
+        "add-module",
+        &[("a.moss", "b = mod \"b\";\nvalue = b.value;\n")],
+    );
+    let mut interpreter = Interpreter::new(workspace.clone());
+    interpreter.init();
+    interpreter.add_src_modules();
+    interpreter.run().await;
+
+    fs::write(workspace.join(SRC_PATH).join("b.moss"), "value = 1;\n").unwrap();
+    interpreter.add_src_modules();
+    interpreter.run().await;
+
+    assert_eq!(value_of(&mut interpreter, "a.moss", "value"), "1");
+
+    fs::remove_dir_all(workspace).unwrap();
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn removed_module_is_not_found_by_importers() {
+    let workspace = workspace(
+        "remove-module",
+        &[
+            ("a.moss", "b = mod \"b\";\nvalue = b.value;\n"),
+            ("b.moss", "value = 1;\n"),
+        ],
+    );
+    let mut interpreter = Interpreter::new(workspace.clone());
+    interpreter.init();
+    interpreter.add_src_modules();
+    interpreter.run().await;
+    assert_eq!(value_of(&mut interpreter, "a.moss", "value"), "1");
+
+    fs::remove_file(workspace.join(SRC_PATH).join("b.moss")).unwrap();
+    let b_path = PathBuf::from(SRC_PATH).join("b.moss");
+    let b_file = interpreter.find_file(&b_path).unwrap();
+    interpreter.remove_file(b_file);
+    interpreter.run().await;
+
+    assert_eq!(interpreter.find_file(&b_path), None);
+    let a_file = interpreter
+        .find_file(PathBuf::from(SRC_PATH).join("a.moss"))
+        .unwrap();
+    let records = interpreter.file_records(a_file);
+    assert!(records.iter().any(|x| x.code == "module-not-found"));
+
+    fs::remove_dir_all(workspace).unwrap();
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn module_with_untaken_branch_is_not_run_again() {
+    let workspace = workspace(
+        "untaken-branch",
+        &[
+            (
+                "a.moss",
+                "picked = if {cond = true; then = 1; else = div {a = 1; b = 0;};};\n",
+            ),
+            ("b.moss", "value = 1;\n"),
+        ],
+    );
+    let mut interpreter = Interpreter::new(workspace.clone());
+    interpreter.init();
+    interpreter.add_src_modules();
+    interpreter.run().await;
+    assert_eq!(value_of(&mut interpreter, "a.moss", "picked"), "1");
+
+    fs::write(workspace.join(SRC_PATH).join("b.moss"), "value = 2;\n").unwrap();
+    let b_file = interpreter
+        .find_file(PathBuf::from(SRC_PATH).join("b.moss"))
+        .unwrap();
+    interpreter.update_file(b_file);
+    fs::write(workspace.join(SRC_PATH).join("c.moss"), "value = 3;\n").unwrap();
+    interpreter.add_src_modules();
+    interpreter.run().await;
+
+    assert_eq!(value_of(&mut interpreter, "a.moss", "picked"), "1");
+    assert_eq!(value_of(&mut interpreter, "b.moss", "value"), "2");
+    assert_eq!(value_of(&mut interpreter, "c.moss", "value"), "3");
+
+    fs::remove_dir_all(workspace).unwrap();
+}
@@ -0,0 +1,125 @@
+//! Golden tests over the workspaces in `language_example/`.
+//!
+//! Every workspace is run and a dump of its element values and diagnostics is compared
+//! against the `dump.expected` beside its `src/`. Run with `MOSS_BLESS=1` to regenerate them.
+
+use std::{
+    env,
+    fmt::Write as _,
+    fs,
+    path::{Path, PathBuf},
+};
+
+use moss_interpreter::{
+    interpreter::{Interpreter, InterpreterLike as _, Node as _, SRC_PATH, file::FileId},
+    utils::contexted::WithContext as _,
+};
+use walkdir::WalkDir;
+
+const EXPECTED_FILE: &str = "dump.expected";
+const BLESS_ENV: &str = "MOSS_BLESS";
+
+fn workspaces() -> Vec<PathBuf> {
+    let root = Path::new(env!("CARGO_MANIFEST_DIR")).join("../language_example");
+    let mut workspaces = WalkDir::new(root)
+        .into_iter()
+        .filter_map(Result::ok)
+        .filter(|entry| entry.file_type().is_dir() && entry.path().join(SRC_PATH).is_dir())
+        .map(|entry| entry.into_path())
+        .collect::<Vec<_>>();
+    workspaces.sort();
+    workspaces
+}
+
+async fn run(workspace: PathBuf) -> Interpreter {
+    let mut interpreter = Interpreter::new(workspace);
+    interpreter.init();
+    interpreter.add_src_modules();
+    interpreter.run().await;
+    interpreter
+}
+
+/// Dumps, for every module file, the named elements of each scope authored in it, headed by
+/// the scope's position, followed by the file's diagnostics. Everything is sorted.
+fn dump(interpreter: &Interpreter) -> String {
+    let mut out = String::new();
+    let mut files = interpreter
+        .files
+        .iter()
+        .filter(|(_, file)| file.is_module.is_some())
+        .map(|(id, _)| id)
+        .collect::<Vec<FileId>>();
+    files.sort_by(|a, b| interpreter.files[*a].path.cmp(&interpreter.files[*b].path));
+    for file_id in files {
+        let file = interpreter.get_file(file_id);
+        writeln!(out, "# {}", file.path.to_string_lossy().replace('\\', "/")).unwrap();
+
+        let mut scopes = vec![];
+        interpreter.visit_file_scopes(file_id, |scope_id| {
+            let source = interpreter.get(scope_id).authored.unwrap().source.source();
+            scopes.push((source.start_byte(), source.start_position(), scope_id));
+        });
+        scopes.sort_by_key(|(start_byte, ..)| *start_byte);
+        for (_, start, scope_id) in scopes {
+            writeln!(out, "[{}:{}]", start.row + 1, start.column + 1).unwrap();
+            let mut elements = interpreter
+                .get(scope_id)
+                .elements
+                .iter()
+                .map(|(key, element_id)| (interpreter.id2str(*key).to_string(), *element_id))
+                .collect::<Vec<_>>();
+            elements.sort_by(|a, b| a.0.cmp(&b.0));
+            for (name, element_id) in elements {
+                match interpreter.get_element_value(element_id) {
+                    Some(value) => writeln!(out, "{} = {}", name, value.with_ctx(interpreter)),
+                    None => writeln!(out, "{} unresolved", name),
+                }
+                .unwrap();
+            }
+        }
+
+        let mut diagnostics = interpreter
+            .file_diagnostics(file_id)
+            .into_iter()
+            .map(|x| {
+                (
+                    x.source.start_byte(),
+                    x.source.start_position(),
+                    format!("{}", x.diagnostic.with_ctx(interpreter)),
+                )
+            })
+            .collect::<Vec<_>>();
+        diagnostics.sort_by(|a, b| (a.0, &a.2).cmp(&(b.0, &b.2)));
+        for (_, start, message) in diagnostics {
+            writeln!(out, "! {}:{}: {}", start.row + 1, start.column + 1, message).unwrap();
+        }
+    }
+    out
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn language_example() {
+    let bless = env::var_os(BLESS_ENV).is_some();
+    let mut mismatched = vec![];
+    for workspace in workspaces() {
+        let actual = dump(&run(workspace.clone()).await);
+        let expected_path = workspace.join(EXPECTED_FILE);
+        if bless {
+            fs::write(&expected_path, &actual).unwrap();
+            continue;
+        }
+        let expected = fs::read_to_string(&expected_path)
+            .unwrap_or_default()
+            .replace("\r\n", "\n");
+        if expected != actual {
+            eprintln!("{}:\n{}", expected_path.display(), actual);
+            mismatched.push(expected_path);
+        }
+    }
+    assert!(
+        mismatched.is_empty(),
+        "mismatched dumps, rerun with `{}=1` to bless them: {:#?}",
+        BLESS_ENV,
+        mismatched
+    );
+}
@@ -0,0 +1,34 @@
+//! How floats are kept and shown.
+
+use moss_interpreter::interpreter::value::Float;
+
+#[test]
+fn float_display_round_trips() {
+    for value in [
+        1.0,
+        0.1,
+        1.0 / 3.0,
+        1e-7,
+        1.5e300,
+        f64::MIN_POSITIVE,
+        f64::MAX,
+    ] {
+        let float = Float::new(value);
+        let text = format!("{}", float);
+        assert!(text.contains(['.', 'e']), "{} reads as an int", text);
+        assert_eq!(text.parse::<f64>().map(Float::new), Ok(float), "{}", text);
+    }
+    assert_eq!(format!("{}", Float::new(f64::INFINITY)), "inf");
+    assert_eq!(format!("{}", Float::new(f64::NEG_INFINITY)), "-inf");
+}
+
+#[test]
+fn nan_is_canonical() {
+    let nan = Float::new(f64::NAN);
+    assert_eq!(Float::new(-f64::NAN), nan);
+    assert_eq!(Float::new(f64::from_bits(0x7ff8_0000_0000_0001)), nan);
+    assert_eq!(Float::new(f64::INFINITY * 0.0), nan);
+    assert!(nan.get().is_nan());
+    assert_eq!(format!("{}", nan), "NaN");
+    assert_ne!(Float::new(0.0), Float::new(-0.0));
+}
@@ -0,0 +1,216 @@
+//! Editor lookups over a resolved workspace.
+
+use std::path::PathBuf;
+
+use moss_interpreter::interpreter::{
+    Interpreter, InterpreterLike as _, InterpreterLikeMut as _, Node as _,
+    file::FileId,
+    query::{Definition, Symbol, edit_distance, find_similar_keys},
+    source::MemorySource,
+};
+
+const A: &str = "b = mod \"b\";\nvalue = b.value;\nother = value;\n";
+const B: &str = "value = 1;\n";
+
+async fn run(files: &[(&str, &str)]) -> Interpreter {
+    let source = MemorySource::new(files.iter().copied());
+    let mut interpreter = Interpreter::new_with_source(PathBuf::new(), Box::new(source));
+    interpreter.init();
+    interpreter.add_src_modules();
+    interpreter.run().await;
+    interpreter
+}
+
+fn file(interpreter: &Interpreter, path: &str) -> FileId {
+    interpreter.find_file(path).unwrap()
+}
+
+/// Text of the key of the element a definition points to, or the path of its file.
+fn describe(interpreter: &Interpreter, definition: Definition) -> String {
+    match definition {
+        Definition::Element(element_id) => {
+            let key = interpreter
+                .get(element_id)
+                .source
+                .unwrap()
+                .key_source
+                .unwrap();
+            let file_id = interpreter.get_element_file(element_id).unwrap();
+            let start = key.start_position();
+            format!(
+                "{}:{}:{}",
+                interpreter.get_file(file_id).path.display(),
+                start.row + 1,
+                start.column + 1
+            )
+        }
+        Definition::File(file_id) => interpreter.get_file(file_id).path.display().to_string(),
+    }
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn definition() {
+    let interpreter = run(&[("src/a.moss", A), ("src/b.moss", B)]).await;
+    let a = file(&interpreter, "src/a.moss");
+    let definition_at =
+        |byte: usize| describe(&interpreter, interpreter.find_definition(a, byte).unwrap());
+    assert_eq!(definition_at(A.find("\"b\"").unwrap()), "src/b.moss");
+    assert_eq!(definition_at(A.find("b.value").unwrap()), "src/a.moss:1:1");
+    assert_eq!(
+        definition_at(A.find(".value").unwrap() + 1),
+        "src/b.moss:1:1"
+    );
+    assert_eq!(definition_at(A.rfind("value").unwrap()), "src/a.moss:2:1");
+    assert_eq!(definition_at(A.find("other").unwrap()), "src/a.moss:3:1");
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn references() {
+    let interpreter = run(&[("src/a.moss", A), ("src/b.moss", B)]).await;
+    let a = file(&interpreter, "src/a.moss");
+    let b = file(&interpreter, "src/b.moss");
+    let references_at = |file_id: FileId, byte: usize| {
+        let Some(Definition::Element(element_id)) = interpreter.find_definition(file_id, byte)
+        else {
+            panic!("no element at {}", byte);
+        };
+        interpreter
+            .find_references(element_id)
+            .into_iter()
+            .map(|(file_id, source)| {
+                let start = source.start_position();
+                format!(
+                    "{}:{}:{}",
+                    interpreter.get_file(file_id).path.display(),
+                    start.row + 1,
+                    start.column + 1
+                )
+            })
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(references_at(b, 0), ["src/a.moss:2:11"]);
+    assert_eq!(
+        references_at(a, A.find("value").unwrap()),
+        ["src/a.moss:3:9"]
+    );
+    assert_eq!(references_at(a, 0), ["src/a.moss:2:9"]);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn completion() {
+    let interpreter = run(&[("src/a.moss", A), ("src/b.moss", B)]).await;
+    let a = file(&interpreter, "src/a.moss");
+    let scope_id = interpreter
+        .find_scope_at(a, A.find("other").unwrap())
+        .unwrap();
+    let names = |elements: Vec<_>| {
+        elements
+            .into_iter()
+            .map(|(name, _)| interpreter.id2str(name).to_string())
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(
+        names(interpreter.get_visible_elements(scope_id)),
+        [
+            "b",
+            "other",
+            "value",
+            "add",
+            "and",
+            "concat",
+            "diagnose",
+            "div",
+            "eq",
+            "false",
+            "filter",
+            "float",
+            "fold",
+            "get",
+            "has",
+            "if",
+            "int",
+            "intersect",
+            "le",
+            "len",
+            "lt",
+            "map",
+            "mod",
+            "mul",
+            "neg",
+            "not",
+            "or",
+            "range",
+            "rem",
+            "select",
+            "sub",
+            "true",
+            "union"
+        ]
+    );
+    let b_scope = interpreter.find_scope_by_path(scope_id, &["b"]).unwrap();
+    assert_eq!(
+        names(
+            interpreter
+                .get(b_scope)
+                .elements
+                .iter()
+                .map(|(k, v)| (*k, *v))
+                .collect()
+        ),
+        ["value"]
+    );
+    assert_eq!(interpreter.get_module_paths(), ["a", "b"]);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn symbols() {
+    let interpreter = run(&[(
+        "src/a.moss",
+        "x = 1;\nf = a -> { y = a; };\ns = { z = { w = 2; }; };\n",
+    )])
+    .await;
+
+    fn outline(interpreter: &Interpreter, symbols: &[Symbol]) -> String {
+        symbols
+            .iter()
+            .map(|symbol| {
+                let name = interpreter.id2str(symbol.name).to_string();
+                if symbol.children.is_empty() {
+                    name
+                } else {
+                    format!("{}({})", name, outline(interpreter, &symbol.children))
+                }
+            })
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+    let a = file(&interpreter, "src/a.moss");
+    assert_eq!(
+        outline(&interpreter, &interpreter.get_file_symbols(a)),
+        "x f(y) s(z(w))"
+    );
+    assert_eq!(interpreter.find_symbols("W").len(), 1);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn failed_find_suggestions() {
+    assert_eq!(edit_distance("kitten", "sitting"), 3);
+    let mut interpreter = run(&[("src/a.moss", "value = 1;\nother = vlaue;\n")]).await;
+
+    let a = file(&interpreter, "src/a.moss");
+    let scope_id = interpreter.find_scope_at(a, 0).unwrap();
+    let name = interpreter.str2id("vlaue");
+    assert_eq!(
+        find_similar_keys(&interpreter, scope_id, name, true)
+            .into_iter()
+            .map(|x| interpreter.id2str(x).to_string())
+            .collect::<Vec<_>>(),
+        ["value"]
+    );
+    let records = interpreter.file_records(a);
+    assert_eq!(records.len(), 1);
+    assert_eq!(
+        records[0].message,
+        "no element `vlaue` in scope; did you mean `value`?"
+    );
+}
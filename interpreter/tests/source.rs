@@ -0,0 +1,74 @@
+//! Running workspaces whose sources aren't read from disk.
+
+use std::path::PathBuf;
+
+use moss_interpreter::{
+    interpreter::{
+        Interpreter, InterpreterLike as _, InterpreterLikeMut as _,
+        source::{DiskSource, MemorySource, OverlaySource},
+    },
+    utils::contexted::WithContext as _,
+};
+
+#[tokio::test(flavor = "multi_thread")]
+async fn memory_source() {
+    let source = MemorySource::new([
+        ("src/a.moss", "b = mod \"b\";\nvalue = b.value;\n"),
+        ("src/b.moss", "value = 1;\n"),
+        ("notes.txt", "not a module"),
+    ]);
+    let mut interpreter = Interpreter::new_with_source(PathBuf::new(), Box::new(source));
+    interpreter.init();
+    interpreter.add_src_modules();
+    interpreter.run().await;
+
+    assert_eq!(interpreter.files.len(), 2);
+    let name = interpreter.str2id("value");
+    let file_id = interpreter.find_file("src/a.moss").unwrap();
+    let module_id = interpreter.get_file(file_id).is_module.unwrap();
+    let scope_id = interpreter.get_module_scope(module_id).unwrap();
+    let element_id = interpreter.get(scope_id).elements[&name];
+    let value = interpreter.get_element_value(element_id).unwrap();
+    assert_eq!(format!("{}", value.with_ctx(&interpreter)), "1");
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn read_error_is_diagnosed() {
+    let mut source = OverlaySource::new(DiskSource::new(PathBuf::from("missing-workspace")));
+    source
+        .buffers
+        .files
+        .insert("src/a.moss".into(), "value = 1;\n".into());
+    let mut interpreter = Interpreter::new_with_source(PathBuf::new(), Box::new(source));
+    interpreter.init();
+    interpreter.add_src_modules();
+    interpreter.add_module(Some("src/missing.moss".into()));
+    interpreter.run().await;
+
+    let file_id = interpreter.find_file("src/a.moss").unwrap();
+    assert!(interpreter.file_records(file_id).is_empty());
+    let file_id = interpreter.find_file("src/missing.moss").unwrap();
+    let records = interpreter.file_records(file_id);
+    assert_eq!(records.len(), 1);
+    assert_eq!(records[0].code, "read-error");
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn grammar_error_is_diagnosed_once() {
+    let source = MemorySource::new([("src/a.moss", "value = ;\nother = 1;\n")]);
+    let mut interpreter = Interpreter::new_with_source(PathBuf::new(), Box::new(source));
+    interpreter.init();
+    interpreter.add_src_modules();
+    interpreter.run().await;
+
+    let file_id = interpreter.find_file("src/a.moss").unwrap();
+    let records = interpreter.file_records(file_id);
+    assert!(!records.is_empty());
+    let mut ranges = records
+        .iter()
+        .map(|x| (x.range.start_byte, x.range.end_byte))
+        .collect::<Vec<_>>();
+    ranges.sort();
+    ranges.dedup();
+    assert_eq!(ranges.len(), records.len());
+}
@@ -1,47 +1,87 @@
 use std::{
-    collections::HashMap,
+    collections::{BTreeMap, HashMap},
     path::{Path, PathBuf},
-    sync::OnceLock,
+    sync::{
+        Arc,
+        atomic::{AtomicBool, AtomicUsize, Ordering},
+    },
 };
 
 use tokio::sync::RwLock;
 use tower_lsp::{
     Client, LanguageServer as LanguageServerLike,
     lsp_types::{
-        CompletionParams, CompletionResponse, Diagnostic as LspDiagnostic, DiagnosticSeverity,
-        DidCloseTextDocumentParams, DidOpenTextDocumentParams, DidSaveTextDocumentParams, Hover,
-        HoverContents, HoverParams, HoverProviderCapability, InitializeParams, InitializeResult,
-        InitializedParams, MarkedString, MessageType, Position as LspPosition, Range as LspRange,
-        SaveOptions, ServerCapabilities, TextDocumentSyncCapability, TextDocumentSyncKind,
-        TextDocumentSyncOptions, TextDocumentSyncSaveOptions, Url,
+        CodeAction, CodeActionKind, CodeActionOptions, CodeActionOrCommand, CodeActionParams,
+        CodeActionProviderCapability, CodeActionResponse, CompletionItem, CompletionItemKind,
+        CompletionOptions, CompletionParams, CompletionResponse, Diagnostic as LspDiagnostic,
+        DiagnosticSeverity, DidChangeTextDocumentParams, DidChangeWatchedFilesParams,
+        DidChangeWatchedFilesRegistrationOptions, DidChangeWorkspaceFoldersParams,
+        DidCloseTextDocumentParams, DidOpenTextDocumentParams, DidSaveTextDocumentParams,
+        DocumentFormattingParams, DocumentSymbol, DocumentSymbolParams, DocumentSymbolResponse,
+        FileChangeType, FileSystemWatcher, GlobPattern, GotoDefinitionParams,
+        GotoDefinitionResponse, Hover, HoverContents, HoverParams, HoverProviderCapability,
+        InitializeParams, InitializeResult, InitializedParams, InlayHint, InlayHintLabel,
+        InlayHintParams, Location, MarkupContent, MarkupKind, MessageType, OneOf,
+        Position as LspPosition, PositionEncodingKind, Range as LspRange, ReferenceParams,
+        Registration, RenameParams, SaveOptions, SemanticTokens, SemanticTokensDelta,
+        SemanticTokensDeltaParams, SemanticTokensFullDeltaResult, SemanticTokensFullOptions,
+        SemanticTokensOptions, SemanticTokensParams, SemanticTokensResult,
+        SemanticTokensServerCapabilities, ServerCapabilities, SymbolInformation, SymbolKind,
+        TextDocumentContentChangeEvent, TextDocumentPositionParams, TextDocumentSyncCapability,
+        TextDocumentSyncKind, TextDocumentSyncOptions, TextDocumentSyncSaveOptions,
+        TextEdit as LspTextEdit, Url, WorkspaceEdit, WorkspaceFoldersServerCapabilities,
+        WorkspaceServerCapabilities, WorkspaceSymbolParams,
     },
 };
+use tree_sitter::Point;
+use type_sitter::HasChild as _;
+use walkdir::WalkDir;
 
 use moss_interpreter::{
+    format::format,
     interpreter::{
-        Id, Interpreter, InterpreterLike, Node, SRC_FILE_EXTENSION, UntypedNode,
-        file::FileId,
-        scope::Scope,
-        value::{self, Value},
+        Id, Interpreter, InterpreterLike, Node, SRC_FILE_EXTENSION, SRC_PATH, UntypedNode,
+        diagnose::{Diagnostic, Located},
+        element::{Element, ElementKey},
+        file::{FileId, TextEdit},
+        query::{Definition, Symbol},
+        unescape,
+        value::Value,
     },
-    utils::{contexted::WithContext as _, erase, erase_mut},
+    utils::{concurrent_string_interner::StringId, contexted::WithContext as _, moss},
 };
-use type_sitter::TreeCursor;
-use walkdir::WalkDir;
+
+use crate::semantic_tokens;
+
+/// Characters of a value shown in an inlay hint before it is cut with `..`.
+const INLAY_HINT_MAX_CHARS: usize = 32;
 
 pub struct LanguageServer {
     pub client: Client,
-    pub interpreter: OnceLock<RwLock<Interpreter>>,
+    /// Interpreter of every workspace found in the workspace folders, by workspace path.
+    pub workspaces: RwLock<BTreeMap<PathBuf, Arc<RwLock<Interpreter>>>>,
     pub opened_files: RwLock<HashMap<Url, File>>,
+    pub semantic_tokens_id: AtomicUsize,
+    /// Whether columns were negotiated in UTF-8 bytes, rather than the default UTF-16 code units.
+    pub utf8: AtomicBool,
+    /// Whether the client accepts `workspace/inlayHint/refresh`.
+    pub inlay_hint_refresh: AtomicBool,
+    /// Whether the client accepts `workspace/semanticTokens/refresh`.
+    pub semantic_tokens_refresh: AtomicBool,
 }
 
 pub struct File {
     pub path: PathBuf,
+    /// Semantic tokens last sent, which delta requests are based on.
+    pub semantic_tokens: Option<SemanticTokens>,
 }
 
 impl File {
     pub fn new(path: PathBuf) -> Self {
-        Self { path }
+        Self {
+            path,
+            semantic_tokens: None,
+        }
     }
 }
 
@@ -49,23 +89,39 @@ impl LanguageServer {
     pub fn new(client: Client) -> Self {
         Self {
             client,
-            interpreter: Default::default(),
+            workspaces: RwLock::new(Default::default()),
             opened_files: RwLock::new(Default::default()),
+            semantic_tokens_id: AtomicUsize::new(0),
+            utf8: AtomicBool::new(false),
+            inlay_hint_refresh: AtomicBool::new(false),
+            semantic_tokens_refresh: AtomicBool::new(false),
         }
     }
+    /// Position of `point`, the point of `byte` in `text`, in the negotiated columns.
+    pub fn point2position(&self, text: &str, byte: usize, point: Point) -> LspPosition {
+        let character = if self.utf8.load(Ordering::Relaxed) {
+            point.column
+        } else {
+            text[byte - point.column..byte].encode_utf16().count()
+        };
+        LspPosition::new(point.row as u32, character as u32)
+    }
+    /// Range of `source`, a node of `text`.
+    pub fn node2range(&self, text: &str, source: UntypedNode<'static>) -> LspRange {
+        LspRange::new(
+            self.point2position(text, source.start_byte(), source.start_position()),
+            self.point2position(text, source.end_byte(), source.end_position()),
+        )
+    }
     pub fn make_diagnostic(
         &self,
+        text: &str,
         source: UntypedNode<'static>,
         message: impl Into<String>,
         severity: DiagnosticSeverity,
     ) -> LspDiagnostic {
-        let source_start = source.start_position();
-        let source_end = source.end_position();
         LspDiagnostic {
-            range: LspRange::new(
-                LspPosition::new(source_start.row as u32, source_start.column as u32),
-                LspPosition::new(source_end.row as u32, source_end.column as u32),
-            ),
+            range: self.node2range(text, source),
             severity: Some(severity),
             code: None,
             code_description: None,
@@ -76,6 +132,23 @@ impl LanguageServer {
             data: None,
         }
     }
+    /// Point of `position` in `text`, with the column counted in bytes as tree-sitter does.
+    pub fn position2point(&self, text: &str, position: LspPosition) -> Point {
+        let character = position.character as usize;
+        if self.utf8.load(Ordering::Relaxed) {
+            return Point::new(position.line as usize, character);
+        }
+        let line = text.split('\n').nth(position.line as usize).unwrap_or("");
+        let mut units = 0;
+        let column = line
+            .char_indices()
+            .find(|(_, x)| {
+                units += x.len_utf16();
+                units > character
+            })
+            .map_or(line.len(), |(byte, _)| byte);
+        Point::new(position.line as usize, column)
+    }
     pub fn uri2path(&self, uri: &Url, interpreter: &Interpreter) -> Option<PathBuf> {
         let raw_path = uri.to_file_path().unwrap();
         Some(
@@ -85,178 +158,523 @@ impl LanguageServer {
                 .to_path_buf(),
         )
     }
-    pub async fn diagnose(&self, uri: Url, path: impl AsRef<Path>, interpreter: &Interpreter) {
-        let mut lsp_diagnostics = Vec::<LspDiagnostic>::new();
-
-        struct Context<'a> {
-            file_id: FileId,
-            ls: &'a LanguageServer,
-            ip: &'a Interpreter,
-            lsp_diagnostics: &'a mut Vec<LspDiagnostic>,
-            cursor: TreeCursor<'static>,
-        }
-        impl<'a> Context<'a> {
-            fn grammar(&mut self) {
-                loop {
-                    let node = self.cursor.node();
-
-                    if node.is_extra() {
-                        self.lsp_diagnostics.push(self.ls.make_diagnostic(
-                            node,
-                            format!("grammar error: extra {}", node.kind()),
-                            DiagnosticSeverity::ERROR,
-                        ));
-                    }
-
-                    if node.is_error() {
-                        self.lsp_diagnostics.push(self.ls.make_diagnostic(
-                            node,
-                            format!("grammar error: error token"),
-                            DiagnosticSeverity::ERROR,
-                        ));
-                    }
-
-                    if node.is_missing() {
-                        self.lsp_diagnostics.push(self.ls.make_diagnostic(
-                            node,
-                            format!("grammar error: missing {}", node.kind()),
-                            DiagnosticSeverity::ERROR,
-                        ));
-                    }
-
-                    if self.cursor.goto_first_child() {
-                        self.grammar();
-                        self.cursor.goto_parent();
-                    }
-
-                    if !self.cursor.goto_next_sibling() {
-                        break;
-                    }
-                }
+    /// Directories under `folder` with `.moss` files in their `src/`, each a workspace. A
+    /// `src/` isn't searched for nested workspaces.
+    pub fn discover_workspaces(folder: &Path) -> Vec<PathBuf> {
+        let is_src_file = |x: &walkdir::DirEntry| {
+            x.path()
+                .extension()
+                .is_some_and(|x| x == SRC_FILE_EXTENSION)
+        };
+        let mut workspaces = vec![];
+        let mut entries = WalkDir::new(folder).sort_by_file_name().into_iter();
+        while let Some(entry) = entries.next() {
+            let Ok(entry) = entry else {
+                continue;
+            };
+            if !entry.file_type().is_dir() {
+                continue;
+            }
+            let name = entry.file_name().to_string_lossy();
+            if entry.depth() > 0 && (name == SRC_PATH || name.starts_with('.')) {
+                entries.skip_current_dir();
+                continue;
             }
-            fn traverse(&mut self, scope_id: Id<Scope>) {
-                let scope_local = unsafe { self.ip.get_local(scope_id) };
-                let scope = self.ip.get(scope_id);
-                if let Some(authored) = scope.authored {
-                    let source = authored.source.source();
-                    for diagnostic in &scope_local.diagnoistics {
-                        self.lsp_diagnostics.push(self.ls.make_diagnostic(
-                            source,
-                            format!("{}", diagnostic.with_ctx(self.ip)),
-                            DiagnosticSeverity::ERROR,
+            let src_path = entry.path().join(SRC_PATH);
+            if WalkDir::new(&src_path)
+                .into_iter()
+                .filter_map(Result::ok)
+                .any(|x| is_src_file(&x))
+            {
+                workspaces.push(entry.into_path());
+            }
+        }
+        workspaces
+    }
+    /// Adds an interpreter, not run yet, for every workspace discovered in `folder`.
+    /// # Returns
+    /// - the interpreters added.
+    pub async fn add_workspace_folder(&self, folder: &Url) -> Vec<Arc<RwLock<Interpreter>>> {
+        let Ok(folder) = folder.to_file_path() else {
+            log::error!("error workspace folder: {folder}");
+            return vec![];
+        };
+        let mut workspaces = self.workspaces.write().await;
+        let mut added = vec![];
+        for workspace_path in Self::discover_workspaces(&folder) {
+            if workspaces.contains_key(&workspace_path) {
+                continue;
+            }
+            let interpreter = Arc::new(RwLock::new(Interpreter::new(workspace_path.clone())));
+            workspaces.insert(workspace_path, interpreter.clone());
+            added.push(interpreter);
+        }
+        added
+    }
+    /// Interpreter of the innermost workspace containing the file of `uri`.
+    pub async fn get_interpreter(&self, uri: &Url) -> Option<Arc<RwLock<Interpreter>>> {
+        let path = uri.to_file_path().ok()?;
+        let workspaces = self.workspaces.read().await;
+        workspaces
+            .iter()
+            .filter(|(workspace_path, _)| path.starts_with(workspace_path))
+            .max_by_key(|(workspace_path, _)| workspace_path.components().count())
+            .map(|(_, interpreter)| interpreter.clone())
+    }
+    pub fn path2uri(path: impl AsRef<Path>, interpreter: &Interpreter) -> Option<Url> {
+        Url::from_file_path(interpreter.workspace_path.join(path)).ok()
+    }
+    /// Finds the file and the byte of an LSP position in a document.
+    pub fn locate(
+        &self,
+        params: &TextDocumentPositionParams,
+        interpreter: &Interpreter,
+    ) -> Option<(FileId, usize)> {
+        let path = self.uri2path(&params.text_document.uri, interpreter)?;
+        let file_id = interpreter.find_file(path)?;
+        let file = interpreter.get_file(file_id);
+        let byte = file.point2byte(self.position2point(&file.text, params.position));
+        Some((file_id, byte))
+    }
+    /// The element named at a position, with its key and the name of every find resolved to
+    /// it, across all modules.
+    pub fn find_occurrences(
+        &self,
+        params: &TextDocumentPositionParams,
+        interpreter: &Interpreter,
+    ) -> Option<(Id<Element>, Vec<(FileId, UntypedNode<'static>)>)> {
+        let (file_id, byte) = self.locate(params, interpreter)?;
+        let Definition::Element(element_id) = interpreter.find_definition(file_id, byte)? else {
+            return None;
+        };
+        let mut sources = vec![];
+        if let Some(key) = interpreter
+            .get(element_id)
+            .source
+            .and_then(|x| x.key_source)
+            && let Some(file_id) = interpreter.get_element_file(element_id)
+        {
+            sources.push((file_id, key.upcast()));
+        }
+        sources.extend(interpreter.find_references(element_id));
+        Some((element_id, sources))
+    }
+    pub fn is_name(name: &str) -> bool {
+        let mut chars = name.chars();
+        chars.next().is_some_and(|x| x.is_alphabetic() || x == '_')
+            && chars.all(|x| x.is_alphanumeric() || x == '_')
+    }
+    pub fn symbol_kind(interpreter: &Interpreter, element_id: Id<Element>) -> SymbolKind {
+        let value_source = interpreter.get(element_id).source.map(|x| x.value_source);
+        match value_source.and_then(|x| x.child().ok()) {
+            Some(moss::ValueChild::Function(_)) => return SymbolKind::FUNCTION,
+            Some(moss::ValueChild::Scope(_)) => return SymbolKind::NAMESPACE,
+            _ => {}
+        }
+        match interpreter.get_element_value(element_id) {
+            Some(Value::Scope(_)) => SymbolKind::MODULE,
+            Some(Value::Function(_) | Value::BuiltinFunction(_)) => SymbolKind::FUNCTION,
+            Some(Value::Int(_) | Value::Float(_)) => SymbolKind::NUMBER,
+            Some(Value::Bool(_)) => SymbolKind::BOOLEAN,
+            Some(Value::String(_)) => SymbolKind::STRING,
+            Some(Value::List(_) | Value::Set(_)) => SymbolKind::ARRAY,
+            _ => SymbolKind::VARIABLE,
+        }
+    }
+    #[allow(deprecated)]
+    pub fn make_document_symbol(
+        &self,
+        interpreter: &Interpreter,
+        text: &str,
+        symbol: Symbol,
+    ) -> DocumentSymbol {
+        let value_source = interpreter
+            .get(symbol.element_id)
+            .source
+            .unwrap()
+            .value_source;
+        let selection_range = self.node2range(text, symbol.key_source);
+        DocumentSymbol {
+            name: interpreter.id2str(symbol.name).to_string(),
+            detail: interpreter
+                .get_element_value(symbol.element_id)
+                .map(|x| format!("{}", x.with_ctx(interpreter))),
+            kind: Self::symbol_kind(interpreter, symbol.element_id),
+            tags: None,
+            deprecated: None,
+            range: LspRange::new(
+                selection_range.start,
+                self.node2range(text, value_source.upcast()).end,
+            ),
+            selection_range,
+            children: Some(
+                symbol
+                    .children
+                    .into_iter()
+                    .map(|x| self.make_document_symbol(interpreter, text, x))
+                    .collect(),
+            ),
+        }
+    }
+    /// Completion of an element's name, tagged with the kind and type of its value.
+    pub fn make_completion_item(
+        interpreter: &Interpreter,
+        name: StringId,
+        element_id: Id<Element>,
+    ) -> CompletionItem {
+        let value = interpreter.get_element_value(element_id);
+        let kind = match value {
+            Some(Value::Scope(_)) => CompletionItemKind::MODULE,
+            Some(Value::Function(_) | Value::BuiltinFunction(_)) => CompletionItemKind::FUNCTION,
+            Some(
+                Value::Int(_)
+                | Value::Float(_)
+                | Value::Bool(_)
+                | Value::String(_)
+                | Value::List(_)
+                | Value::Set(_),
+            ) => CompletionItemKind::VALUE,
+            Some(
+                Value::IntType(_)
+                | Value::FloatType(_)
+                | Value::BoolType(_)
+                | Value::StringType(_)
+                | Value::ListType(_)
+                | Value::SetType(_)
+                | Value::ScopeType(_)
+                | Value::ElementType(_)
+                | Value::FunctionType(_)
+                | Value::TypeType(_),
+            ) => CompletionItemKind::CLASS,
+            _ => CompletionItemKind::VARIABLE,
+        };
+        CompletionItem {
+            label: interpreter.id2str(name).to_string(),
+            kind: Some(kind),
+            detail: value
+                .and_then(|x| x.get_type())
+                .map(|x| format!("{}", x.with_ctx(interpreter))),
+            ..Default::default()
+        }
+    }
+    /// Quick fixes of a diagnostic in `file_id`, each a title and the edits to that file.
+    pub fn make_quick_fixes(
+        &self,
+        interpreter: &Interpreter,
+        file_id: FileId,
+        located: &Located,
+    ) -> Vec<(String, Vec<LspTextEdit>)> {
+        let mut fixes = vec![];
+        let Some(element_id) = located.element_id else {
+            return fixes;
+        };
+        let Some(source) = interpreter.get(element_id).source else {
+            return fixes;
+        };
+        let text = &interpreter.get_file(file_id).text;
+        let node2position = |node: tree_sitter::Node| {
+            self.point2position(text, node.start_byte(), node.start_position())
+        };
+        match located.diagnostic {
+            Diagnostic::FailedFindElement {
+                name,
+                candidates,
+                is_field,
+            } => {
+                if let Some(name_source) = interpreter.get_find_name_source(element_id) {
+                    for candidate in candidates {
+                        let candidate = interpreter.id2str(*candidate).to_string();
+                        fixes.push((
+                            format!("did you mean `{}`", candidate),
+                            vec![LspTextEdit::new(
+                                self.node2range(text, name_source),
+                                candidate,
+                            )],
                         ));
                     }
                 }
-                for element_id in scope
-                    .elements
-                    .values()
-                    .chain(scope.temp_elements.iter())
-                    .copied()
+                // the element is created just before the assign the find is authored in.
+                let mut assign = Some(*source.value_source.raw());
+                while let Some(node) = assign
+                    && node.kind() != "assign"
                 {
-                    let element_local = unsafe { self.ip.get_local(element_id) };
-                    let element = self.ip.get(element_id);
-                    if let Some(source) = &element.source {
-                        for diagnostic in &element_local.diagnoistics {
-                            let source = if diagnostic.is_key() {
-                                source.key_source.unwrap().upcast()
-                            } else {
-                                source.value_source.upcast()
-                            };
-                            self.lsp_diagnostics.push(self.ls.make_diagnostic(
-                                source,
-                                format!("{}", diagnostic.with_ctx(self.ip)),
-                                DiagnosticSeverity::ERROR,
-                            ));
-                        }
-                        if let Some(key_node) = source.key_source {
-                            self.lsp_diagnostics.push(self.ls.make_diagnostic(
-                                key_node.upcast(),
-                                format!(
-                                        "{}",
-                                        element_local
-                                            .value
-                                            .unwrap_or(Value::Error(value::Error))
-                                            .with_ctx(self.ip)
-                                    ),
-                                DiagnosticSeverity::HINT,
-                            ));
-                        }
-                    }
+                    assign = node.parent();
                 }
-                for child_id in scope_local.children.iter().copied() {
-                    let child = self.ip.get(child_id);
-                    if let Some(file) = child.get_file()
-                        && file == self.file_id
-                    {
-                        self.traverse(child_id);
-                    }
+                if !*is_field && let Some(assign) = assign {
+                    let name = interpreter.id2str(*name).to_string();
+                    let line_start = text[..assign.start_byte()].rfind('\n').map_or(0, |x| x + 1);
+                    let indent = &text[line_start..assign.start_byte()];
+                    let new_text = if indent.trim().is_empty() {
+                        format!("{} = {{}};\n{}", name, indent)
+                    } else {
+                        format!("{} = {{}}; ", name)
+                    };
+                    let position = node2position(assign);
+                    fixes.push((
+                        format!("create element `{}` in enclosing scope", name),
+                        vec![LspTextEdit::new(
+                            LspRange::new(position, position),
+                            new_text,
+                        )],
+                    ));
+                }
+            }
+            Diagnostic::RedundantElementKey { other } => {
+                let (Some(key_source), Some(other_key_source)) = (
+                    source.key_source,
+                    interpreter.get(*other).source.and_then(|x| x.key_source),
+                ) else {
+                    return fixes;
+                };
+                // only the later of the two is the duplicate.
+                if key_source.start_byte() < other_key_source.start_byte() {
+                    return fixes;
+                }
+                let name = &text[key_source.start_byte()..key_source.end_byte()];
+                let scope = interpreter.get(source.scope);
+                let new_name = (2..)
+                    .map(|i| format!("{}{}", name, i))
+                    .find(|x| {
+                        interpreter
+                            .strings
+                            .get(x)
+                            .is_none_or(|x| !scope.elements.contains_key(&x))
+                    })
+                    .unwrap();
+                fixes.push((
+                    format!("rename duplicate to `{}`", new_name),
+                    vec![LspTextEdit::new(
+                        self.node2range(text, key_source.upcast()),
+                        new_name,
+                    )],
+                ));
+            }
+            Diagnostic::StringEscapeError {} => {
+                let Ok(moss::ValueChild::String(string)) = source.value_source.child() else {
+                    return fixes;
+                };
+                let string = *string.raw();
+                let mut cursor = string.walk();
+                let edits = string
+                    .named_children(&mut cursor)
+                    .filter_map(|content| content.named_child(0))
+                    .filter(|x| {
+                        x.kind() == "string_escape" && unescape(&text[x.byte_range()]).is_none()
+                    })
+                    .map(|x| {
+                        let position = node2position(x);
+                        LspTextEdit::new(LspRange::new(position, position), "\\".to_string())
+                    })
+                    .collect::<Vec<_>>();
+                if !edits.is_empty() {
+                    fixes.push(("escape backslash".to_string(), edits));
                 }
             }
+            _ => {}
+        }
+        fixes
+    }
+    /// Computes the semantic tokens of the opened `uri`, remembering them for later deltas.
+    /// # Returns
+    /// - the tokens with the ones previously sent, if any.
+    pub async fn make_semantic_tokens(
+        &self,
+        uri: &Url,
+    ) -> Option<(SemanticTokens, Option<SemanticTokens>)> {
+        let interpreter = self.get_interpreter(uri).await?;
+        let interpreter = interpreter.read().await;
+        let mut files = self.opened_files.write().await;
+        let file = files.get_mut(uri)?;
+        let file_id = interpreter.find_file(&file.path)?;
+        let tokens = SemanticTokens {
+            result_id: Some(
+                self.semantic_tokens_id
+                    .fetch_add(1, Ordering::Relaxed)
+                    .to_string(),
+            ),
+            data: semantic_tokens::file_tokens(
+                &interpreter,
+                file_id,
+                self.utf8.load(Ordering::Relaxed),
+            ),
+        };
+        let previous = file.semantic_tokens.replace(tokens.clone());
+        Some((tokens, previous))
+    }
+    /// Markdown describing the element: its value, the value's type and, for a find, the
+    /// scope the name resolved into.
+    pub fn describe_element(interpreter: &Interpreter, element_id: Id<Element>) -> String {
+        let element = interpreter.get(element_id);
+        let value = interpreter.get_element_value(element_id);
+        let mut text = String::from("```moss\n");
+        if let ElementKey::Name(name) = element.key {
+            text.push_str(&format!("{} = ", &*interpreter.id2str(name)));
         }
+        match value {
+            Some(value) => text.push_str(&format!("{}", value.with_ctx(interpreter))),
+            None => text.push_str("unresolved"),
+        }
+        text.push_str("\n```");
+        if let Some(r#type) = value.and_then(|x| x.get_type()) {
+            text.push_str(&format!("\n\ntype: `{}`", r#type.with_ctx(interpreter)));
+        }
+        if let Some(found_id) = interpreter.get_found_element(element_id) {
+            text.push_str(&format!(
+                "\n\nfound in scope at `{}`",
+                interpreter.display_element_scope(found_id)
+            ));
+        }
+        text
+    }
+    /// Hints `= <value>` after the key of every named element of `file_id` inside `range`.
+    pub fn make_inlay_hints(
+        &self,
+        interpreter: &Interpreter,
+        file_id: FileId,
+        range: LspRange,
+    ) -> Vec<InlayHint> {
+        let text = &interpreter.get_file(file_id).text;
+        let mut hints = vec![];
+        interpreter.visit_file_scopes(file_id, |scope_id| {
+            for element_id in interpreter.get(scope_id).elements.values().copied() {
+                let element = interpreter.get(element_id);
+                let Some(key_node) = element.source.and_then(|x| x.key_source) else {
+                    continue;
+                };
+                let position = self.node2range(text, key_node.upcast()).end;
+                if position < range.start || range.end < position {
+                    continue;
+                }
+                let value = match interpreter.get_element_value(element_id) {
+                    Some(value) => format!("{}", value.with_ctx(interpreter)),
+                    None => "unresolved".to_string(),
+                };
+                let line = value.lines().next().unwrap_or("");
+                let value =
+                    if line.chars().count() > INLAY_HINT_MAX_CHARS || line.len() < value.len() {
+                        format!(
+                            "{}..",
+                            line.chars().take(INLAY_HINT_MAX_CHARS).collect::<String>()
+                        )
+                    } else {
+                        value
+                    };
+                hints.push(InlayHint {
+                    position,
+                    label: InlayHintLabel::String(format!("= {}", value)),
+                    kind: None,
+                    text_edits: None,
+                    tooltip: None,
+                    padding_left: Some(true),
+                    padding_right: Some(true),
+                    data: None,
+                });
+            }
+        });
+        hints.sort_by_key(|x| x.position);
+        hints
+    }
+    pub async fn diagnose(&self, uri: Url, path: impl AsRef<Path>, interpreter: &Interpreter) {
+        let mut lsp_diagnostics = Vec::<LspDiagnostic>::new();
+
         let Some(file_id) = interpreter.find_file(path) else {
             return;
         };
-
         let file = interpreter.get_file(file_id);
-        let Some(module_id) = file.is_module else {
+        if file.is_module.is_none() {
             return;
-        };
-        let module = interpreter.get_module(module_id);
-        let scope_id = interpreter
-            .get_element_value(module.root_scope.unwrap())
-            .unwrap()
-            .as_scope()
-            .unwrap()
-            .0;
-
-        let mut context = Context {
-            file_id,
-            ls: self,
-            ip: interpreter,
-            lsp_diagnostics: &mut lsp_diagnostics,
-            cursor: erase(file).tree.walk(),
-        };
+        }
 
-        context.grammar();
-        context.traverse(scope_id);
+        for located in interpreter.file_diagnostics(file_id) {
+            lsp_diagnostics.push(self.make_diagnostic(
+                &file.text,
+                located.source,
+                format!("{}", located.diagnostic.with_ctx(interpreter)),
+                DiagnosticSeverity::ERROR,
+            ));
+        }
 
         self.client
             .publish_diagnostics(uri, lsp_diagnostics, None)
             .await;
     }
+    /// Loads every module of the workspace of `interpreter` from scratch and runs them.
+    pub async fn load(interpreter: &RwLock<Interpreter>) {
+        let mut interpreter = interpreter.write().await;
+        interpreter.clear();
+        interpreter.init();
+        interpreter.add_src_modules();
+        interpreter.run().await;
+    }
     pub async fn run(&self) {
+        let interpreters = self
+            .workspaces
+            .read()
+            .await
+            .values()
+            .cloned()
+            .collect::<Vec<_>>();
+        for interpreter in interpreters {
+            Self::load(&interpreter).await;
+        }
+        self.diagnose_opened_files().await;
+    }
+    /// Applies `changes` to the buffer of `uri`, then re-runs and diagnoses the opened files.
+    pub async fn edit(&self, uri: &Url, changes: Vec<TextDocumentContentChangeEvent>) {
         {
-            let Some(interpreter) = self.interpreter.get() else {
+            let Some(interpreter) = self.get_interpreter(uri).await else {
                 return;
             };
             let mut interpreter = interpreter.write().await;
-            interpreter.clear();
-            interpreter.init();
-            for entry in WalkDir::new(interpreter.get_src_path())
-                .into_iter()
-                .filter_map(Result::ok)
-            {
-                let path = entry.path();
-                if path.is_file() {
-                    if let Some(extension) = path.extension() {
-                        if extension == SRC_FILE_EXTENSION {
-                            let path = path
-                                .strip_prefix(interpreter.get_worksapce_path())
-                                .unwrap()
-                                .to_path_buf();
-                            interpreter.add_module(Some(path));
-                        }
-                    }
-                }
+            let files = self.opened_files.read().await;
+            let Some(file) = files.get(uri) else {
+                return;
+            };
+            let Some(file_id) = interpreter.find_file(&file.path) else {
+                return;
+            };
+            // the range of a change is in the text left by the changes before it.
+            for change in changes {
+                let text = &interpreter.get_file(file_id).text;
+                let range = change.range.map(|x| {
+                    (
+                        self.position2point(text, x.start),
+                        self.position2point(text, x.end),
+                    )
+                });
+                let text = change.text;
+                interpreter.edit_file(file_id, [TextEdit { range, text }]);
             }
             interpreter.run().await;
         }
+        self.diagnose_opened_files().await;
+    }
+    pub async fn diagnose_opened_files(&self) {
+        // released before any interpreter is locked, which is always taken first elsewhere.
+        let files = self
+            .opened_files
+            .read()
+            .await
+            .iter()
+            .map(|(uri, file)| (uri.clone(), file.path.clone()))
+            .collect::<Vec<_>>();
+        for (uri, path) in files {
+            let Some(interpreter) = self.get_interpreter(&uri).await else {
+                continue;
+            };
+            let interpreter = interpreter.read().await;
+            self.diagnose(uri, &path, &*interpreter).await
+        }
+        // values shown as inlay hints and semantic tokens may have changed with the run.
+        if self.inlay_hint_refresh.load(Ordering::Relaxed)
+            && let Err(err) = self.client.inlay_hint_refresh().await
         {
-            let interpreter = self.interpreter.get().unwrap().read().await;
-            let files = self.opened_files.read().await;
-            for (uri, file) in files.iter() {
-                self.diagnose(uri.clone(), &file.path, &*interpreter).await
-            }
+            log::error!("inlay hint refresh: {err}");
+        }
+        if self.semantic_tokens_refresh.load(Ordering::Relaxed)
+            && let Err(err) = self.client.semantic_tokens_refresh().await
+        {
+            log::error!("semantic tokens refresh: {err}");
         }
     }
 }
@@ -267,34 +685,46 @@ impl LanguageServerLike for LanguageServer {
         &self,
         params: InitializeParams,
     ) -> tower_lsp::jsonrpc::Result<InitializeResult> {
-        let uri = params.root_uri.as_ref().and_then(|_| {
-            params
-                .workspace_folders
-                .as_ref()
-                .map(|x| x.first().map(|x| &x.uri))
-                .flatten()
-        });
-        if let Some(uri) = uri {
-            let workspace_path = uri.to_file_path().unwrap_or_else(|_| {
-                log::error!("error workspace: {uri}");
-                "err".into()
-            });
-            if let Err(_) = self
-                .interpreter
-                .set(RwLock::new(Interpreter::new(workspace_path)))
-            {
-                log::error!("re-initialize interpreter");
-            }
-        } else {
+        let folders = match &params.workspace_folders {
+            Some(folders) => folders.iter().map(|x| x.uri.clone()).collect(),
+            None => params.root_uri.clone().into_iter().collect::<Vec<_>>(),
+        };
+        for folder in &folders {
+            self.add_workspace_folder(folder).await;
+        }
+        if self.workspaces.read().await.is_empty() {
             log::error!("no workspace");
         }
 
+        let utf8 = params
+            .capabilities
+            .general
+            .as_ref()
+            .and_then(|x| x.position_encodings.as_ref())
+            .is_some_and(|x| x.contains(&PositionEncodingKind::UTF8));
+        self.utf8.store(utf8, Ordering::Relaxed);
+        let workspace = params.capabilities.workspace.as_ref();
+        self.inlay_hint_refresh.store(
+            workspace
+                .and_then(|x| x.inlay_hint.as_ref())
+                .and_then(|x| x.refresh_support)
+                .unwrap_or(false),
+            Ordering::Relaxed,
+        );
+        self.semantic_tokens_refresh.store(
+            workspace
+                .and_then(|x| x.semantic_tokens.as_ref())
+                .and_then(|x| x.refresh_support)
+                .unwrap_or(false),
+            Ordering::Relaxed,
+        );
         Ok(InitializeResult {
             capabilities: ServerCapabilities {
+                position_encoding: utf8.then_some(PositionEncodingKind::UTF8),
                 text_document_sync: Some(TextDocumentSyncCapability::Options(
                     TextDocumentSyncOptions {
                         open_close: Some(true),
-                        change: Some(TextDocumentSyncKind::NONE),
+                        change: Some(TextDocumentSyncKind::INCREMENTAL),
                         will_save: Some(false),
                         will_save_wait_until: Some(false),
                         save: Some(TextDocumentSyncSaveOptions::SaveOptions(SaveOptions {
@@ -303,7 +733,39 @@ impl LanguageServerLike for LanguageServer {
                     },
                 )),
                 hover_provider: Some(HoverProviderCapability::Simple(true)),
-                completion_provider: None,
+                definition_provider: Some(OneOf::Left(true)),
+                references_provider: Some(OneOf::Left(true)),
+                document_symbol_provider: Some(OneOf::Left(true)),
+                workspace_symbol_provider: Some(OneOf::Left(true)),
+                inlay_hint_provider: Some(OneOf::Left(true)),
+                semantic_tokens_provider: Some(
+                    SemanticTokensServerCapabilities::SemanticTokensOptions(
+                        SemanticTokensOptions {
+                            legend: semantic_tokens::legend(),
+                            full: Some(SemanticTokensFullOptions::Delta { delta: Some(true) }),
+                            ..Default::default()
+                        },
+                    ),
+                ),
+                rename_provider: Some(OneOf::Left(true)),
+                workspace: Some(WorkspaceServerCapabilities {
+                    workspace_folders: Some(WorkspaceFoldersServerCapabilities {
+                        supported: Some(true),
+                        change_notifications: Some(OneOf::Left(true)),
+                    }),
+                    file_operations: None,
+                }),
+                document_formatting_provider: Some(OneOf::Left(true)),
+                code_action_provider: Some(CodeActionProviderCapability::Options(
+                    CodeActionOptions {
+                        code_action_kinds: Some(vec![CodeActionKind::QUICKFIX]),
+                        ..Default::default()
+                    },
+                )),
+                completion_provider: Some(CompletionOptions {
+                    trigger_characters: Some(vec![".".to_string(), "\"".to_string()]),
+                    ..Default::default()
+                }),
                 ..ServerCapabilities::default()
             },
             server_info: None,
@@ -314,6 +776,20 @@ impl LanguageServerLike for LanguageServer {
         self.client
             .log_message(MessageType::INFO, "Moss Language Server initialized")
             .await;
+        let options = DidChangeWatchedFilesRegistrationOptions {
+            watchers: vec![FileSystemWatcher {
+                glob_pattern: GlobPattern::String(format!("**/*.{}", SRC_FILE_EXTENSION)),
+                kind: None,
+            }],
+        };
+        let registration = Registration {
+            id: "moss-watched-files".to_string(),
+            method: "workspace/didChangeWatchedFiles".to_string(),
+            register_options: Some(serde_json::to_value(options).unwrap()),
+        };
+        if let Err(err) = self.client.register_capability(vec![registration]).await {
+            log::error!("register watched files: {err}");
+        }
         self.run().await;
     }
 
@@ -322,11 +798,11 @@ impl LanguageServerLike for LanguageServer {
     }
 
     async fn did_open(&self, params: DidOpenTextDocumentParams) {
-        let Some(interpreter) = self.interpreter.get() else {
+        let Some(interpreter) = self.get_interpreter(&params.text_document.uri).await else {
             return;
         };
-        let interpreter = interpreter.read().await;
-        let interpreter = &*interpreter;
+        let guard = interpreter.read().await;
+        let interpreter = &*guard;
         let mut files = self.opened_files.write().await;
 
         let uri = params.text_document.uri;
@@ -334,47 +810,541 @@ impl LanguageServerLike for LanguageServer {
             return;
         };
 
+        let text = params.text_document.text;
+        let is_modified = interpreter
+            .find_file(&path)
+            .is_some_and(|x| interpreter.get_file(x).text != text);
         files.insert(uri.clone(), File::new(path.clone()));
+        drop(files);
 
-        self.diagnose(uri, &path, &*interpreter).await;
+        if is_modified {
+            drop(guard);
+            let change = TextDocumentContentChangeEvent {
+                range: None,
+                range_length: None,
+                text,
+            };
+            self.edit(&uri, vec![change]).await;
+        } else {
+            self.diagnose(uri, &path, &*interpreter).await;
+        }
+    }
+
+    async fn did_change(&self, params: DidChangeTextDocumentParams) {
+        self.edit(&params.text_document.uri, params.content_changes)
+            .await;
     }
 
     async fn did_save(&self, params: DidSaveTextDocumentParams) {
         {
-            let mut interpreter = self.interpreter.get().unwrap().write().await;
+            let uri = params.text_document.uri;
+            let Some(interpreter) = self.get_interpreter(&uri).await else {
+                return;
+            };
+            let mut interpreter = interpreter.write().await;
             let interpreter = &mut *interpreter;
             let files = self.opened_files.read().await;
 
-            let uri = params.text_document.uri;
-            let file = files.get(&uri).unwrap();
-            let path = &file.path;
-            let Some(file) = interpreter.find_file(path) else {
+            let Some(file) = files.get(&uri) else {
                 return;
             };
-            let file = erase_mut(interpreter).get_file_mut(file);
-            file.update(interpreter);
+            let path = &file.path;
+            if interpreter.find_file(path).is_none() {
+                return;
+            }
+            // the buffer is already in sync through `did_change`, only new files are picked up.
+            interpreter.add_src_modules();
+            interpreter.run().await;
         }
-        self.run().await;
+        self.diagnose_opened_files().await;
     }
 
-    async fn hover(&self, _params: HoverParams) -> tower_lsp::jsonrpc::Result<Option<Hover>> {
-        let contents = HoverContents::Scalar(MarkedString::String("kkk".to_string()));
+    async fn hover(&self, params: HoverParams) -> tower_lsp::jsonrpc::Result<Option<Hover>> {
+        let Some(interpreter) = self
+            .get_interpreter(&params.text_document_position_params.text_document.uri)
+            .await
+        else {
+            return Ok(None);
+        };
+        let interpreter = interpreter.read().await;
+        let Some((file_id, byte)) =
+            self.locate(&params.text_document_position_params, &interpreter)
+        else {
+            return Ok(None);
+        };
+        let Some((element_id, source)) = interpreter.find_element_at(file_id, byte) else {
+            return Ok(None);
+        };
         Ok(Some(Hover {
-            contents,
-            range: None,
+            contents: HoverContents::Markup(MarkupContent {
+                kind: MarkupKind::Markdown,
+                value: Self::describe_element(&interpreter, element_id),
+            }),
+            range: Some(self.node2range(&interpreter.get_file(file_id).text, source)),
         }))
     }
 
+    async fn goto_definition(
+        &self,
+        params: GotoDefinitionParams,
+    ) -> tower_lsp::jsonrpc::Result<Option<GotoDefinitionResponse>> {
+        let Some(interpreter) = self
+            .get_interpreter(&params.text_document_position_params.text_document.uri)
+            .await
+        else {
+            return Ok(None);
+        };
+        let interpreter = interpreter.read().await;
+        let Some((file_id, byte)) =
+            self.locate(&params.text_document_position_params, &interpreter)
+        else {
+            return Ok(None);
+        };
+        let Some(definition) = interpreter.find_definition(file_id, byte) else {
+            return Ok(None);
+        };
+        let (file_id, range) = match definition {
+            Definition::Element(element_id) => {
+                let Some(source) = interpreter.get(element_id).source else {
+                    return Ok(None);
+                };
+                let source = source
+                    .key_source
+                    .map_or(source.value_source.upcast(), |x| x.upcast());
+                let Some(file_id) = interpreter.get_element_file(element_id) else {
+                    return Ok(None);
+                };
+                let text = &interpreter.get_file(file_id).text;
+                (file_id, self.node2range(text, source))
+            }
+            Definition::File(file_id) => (file_id, LspRange::default()),
+        };
+        let Some(uri) = Self::path2uri(&interpreter.get_file(file_id).path, &interpreter) else {
+            return Ok(None);
+        };
+        Ok(Some(GotoDefinitionResponse::Scalar(Location::new(
+            uri, range,
+        ))))
+    }
+
+    async fn references(
+        &self,
+        params: ReferenceParams,
+    ) -> tower_lsp::jsonrpc::Result<Option<Vec<Location>>> {
+        let Some(interpreter) = self
+            .get_interpreter(&params.text_document_position.text_document.uri)
+            .await
+        else {
+            return Ok(None);
+        };
+        let interpreter = interpreter.read().await;
+        let Some((element_id, sources)) =
+            self.find_occurrences(&params.text_document_position, &interpreter)
+        else {
+            return Ok(None);
+        };
+        let key = interpreter
+            .get(element_id)
+            .source
+            .and_then(|x| x.key_source)
+            .map(|x| x.upcast());
+        let locations = sources
+            .into_iter()
+            .filter(|(_, source)| params.context.include_declaration || Some(*source) != key)
+            .filter_map(|(file_id, source)| {
+                let uri = Self::path2uri(&interpreter.get_file(file_id).path, &interpreter)?;
+                let text = &interpreter.get_file(file_id).text;
+                Some(Location::new(uri, self.node2range(text, source)))
+            })
+            .collect();
+        Ok(Some(locations))
+    }
+
+    async fn rename(
+        &self,
+        params: RenameParams,
+    ) -> tower_lsp::jsonrpc::Result<Option<WorkspaceEdit>> {
+        let Some(interpreter) = self
+            .get_interpreter(&params.text_document_position.text_document.uri)
+            .await
+        else {
+            return Ok(None);
+        };
+        let interpreter = interpreter.read().await;
+        let new_name = params.new_name;
+        if !Self::is_name(&new_name) {
+            return Err(tower_lsp::jsonrpc::Error::invalid_params(format!(
+                "`{}` is not a name",
+                new_name
+            )));
+        }
+        let Some((_, sources)) =
+            self.find_occurrences(&params.text_document_position, &interpreter)
+        else {
+            return Ok(None);
+        };
+        let mut changes = HashMap::<Url, Vec<LspTextEdit>>::new();
+        for (file_id, source) in sources {
+            let Some(uri) = Self::path2uri(&interpreter.get_file(file_id).path, &interpreter)
+            else {
+                continue;
+            };
+            let range = self.node2range(&interpreter.get_file(file_id).text, source);
+            changes
+                .entry(uri)
+                .or_default()
+                .push(LspTextEdit::new(range, new_name.clone()));
+        }
+        Ok(Some(WorkspaceEdit::new(changes)))
+    }
+
+    async fn formatting(
+        &self,
+        params: DocumentFormattingParams,
+    ) -> tower_lsp::jsonrpc::Result<Option<Vec<LspTextEdit>>> {
+        let Some(interpreter) = self.get_interpreter(&params.text_document.uri).await else {
+            return Ok(None);
+        };
+        let interpreter = interpreter.read().await;
+        let Some(path) = self.uri2path(&params.text_document.uri, &interpreter) else {
+            return Ok(None);
+        };
+        let Some(file_id) = interpreter.find_file(path) else {
+            return Ok(None);
+        };
+        let file = interpreter.get_file(file_id);
+        // a document with syntax errors is left alone rather than half formatted.
+        let Some(formatted) = format(&file.text) else {
+            return Ok(None);
+        };
+        if formatted == file.text {
+            return Ok(Some(vec![]));
+        }
+        let end = file.byte2point(file.text.len());
+        let range = LspRange::new(
+            LspPosition::new(0, 0),
+            self.point2position(&file.text, file.text.len(), end),
+        );
+        Ok(Some(vec![LspTextEdit::new(range, formatted)]))
+    }
+
+    async fn code_action(
+        &self,
+        params: CodeActionParams,
+    ) -> tower_lsp::jsonrpc::Result<Option<CodeActionResponse>> {
+        let Some(interpreter) = self.get_interpreter(&params.text_document.uri).await else {
+            return Ok(None);
+        };
+        let interpreter = interpreter.read().await;
+        let uri = params.text_document.uri;
+        let Some(path) = self.uri2path(&uri, &interpreter) else {
+            return Ok(None);
+        };
+        let Some(file_id) = interpreter.find_file(path) else {
+            return Ok(None);
+        };
+        let mut actions = vec![];
+        for located in interpreter.file_diagnostics(file_id) {
+            let range = self.node2range(&interpreter.get_file(file_id).text, located.source);
+            if range.end < params.range.start || params.range.end < range.start {
+                continue;
+            }
+            let message = format!("{}", located.diagnostic.with_ctx(&*interpreter));
+            let diagnostics = params
+                .context
+                .diagnostics
+                .iter()
+                .filter(|x| x.range == range && x.message == message)
+                .cloned()
+                .collect::<Vec<_>>();
+            for (title, edits) in self.make_quick_fixes(&interpreter, file_id, &located) {
+                actions.push(CodeActionOrCommand::CodeAction(CodeAction {
+                    title,
+                    kind: Some(CodeActionKind::QUICKFIX),
+                    diagnostics: Some(diagnostics.clone()),
+                    edit: Some(WorkspaceEdit::new(HashMap::from([(uri.clone(), edits)]))),
+                    ..Default::default()
+                }));
+            }
+        }
+        Ok(Some(actions))
+    }
+
+    async fn document_symbol(
+        &self,
+        params: DocumentSymbolParams,
+    ) -> tower_lsp::jsonrpc::Result<Option<DocumentSymbolResponse>> {
+        let Some(interpreter) = self.get_interpreter(&params.text_document.uri).await else {
+            return Ok(None);
+        };
+        let interpreter = interpreter.read().await;
+        let Some(path) = self.uri2path(&params.text_document.uri, &interpreter) else {
+            return Ok(None);
+        };
+        let Some(file_id) = interpreter.find_file(path) else {
+            return Ok(None);
+        };
+        let text = &interpreter.get_file(file_id).text;
+        let symbols = interpreter
+            .get_file_symbols(file_id)
+            .into_iter()
+            .map(|x| self.make_document_symbol(&interpreter, text, x))
+            .collect();
+        Ok(Some(DocumentSymbolResponse::Nested(symbols)))
+    }
+
+    #[allow(deprecated)]
+    async fn symbol(
+        &self,
+        params: WorkspaceSymbolParams,
+    ) -> tower_lsp::jsonrpc::Result<Option<Vec<SymbolInformation>>> {
+        let interpreters = self
+            .workspaces
+            .read()
+            .await
+            .values()
+            .cloned()
+            .collect::<Vec<_>>();
+        let mut symbols = vec![];
+        for interpreter in interpreters {
+            let interpreter = interpreter.read().await;
+            symbols.extend(
+                interpreter
+                    .find_symbols(&params.query)
+                    .into_iter()
+                    .filter_map(|(file_id, element_id)| {
+                        let element = interpreter.get(element_id);
+                        let key_source = element.source?.key_source?;
+                        let path = &interpreter.get_file(file_id).path;
+                        Some(SymbolInformation {
+                            name: interpreter.id2str(*element.key.as_name().ok()?).to_string(),
+                            kind: Self::symbol_kind(&interpreter, element_id),
+                            tags: None,
+                            deprecated: None,
+                            location: Location::new(
+                                Self::path2uri(path, &interpreter)?,
+                                self.node2range(
+                                    &interpreter.get_file(file_id).text,
+                                    key_source.upcast(),
+                                ),
+                            ),
+                            container_name: Some(path.to_string_lossy().replace('\\', "/")),
+                        })
+                    }),
+            );
+        }
+        Ok(Some(symbols))
+    }
+
+    async fn inlay_hint(
+        &self,
+        params: InlayHintParams,
+    ) -> tower_lsp::jsonrpc::Result<Option<Vec<InlayHint>>> {
+        let Some(interpreter) = self.get_interpreter(&params.text_document.uri).await else {
+            return Ok(None);
+        };
+        let interpreter = interpreter.read().await;
+        let Some(path) = self.uri2path(&params.text_document.uri, &interpreter) else {
+            return Ok(None);
+        };
+        let Some(file_id) = interpreter.find_file(path) else {
+            return Ok(None);
+        };
+        Ok(Some(self.make_inlay_hints(
+            &interpreter,
+            file_id,
+            params.range,
+        )))
+    }
+
+    async fn semantic_tokens_full(
+        &self,
+        params: SemanticTokensParams,
+    ) -> tower_lsp::jsonrpc::Result<Option<SemanticTokensResult>> {
+        let Some((tokens, _)) = self.make_semantic_tokens(&params.text_document.uri).await else {
+            return Ok(None);
+        };
+        Ok(Some(SemanticTokensResult::Tokens(tokens)))
+    }
+
+    async fn semantic_tokens_full_delta(
+        &self,
+        params: SemanticTokensDeltaParams,
+    ) -> tower_lsp::jsonrpc::Result<Option<SemanticTokensFullDeltaResult>> {
+        let Some((tokens, previous)) = self.make_semantic_tokens(&params.text_document.uri).await
+        else {
+            return Ok(None);
+        };
+        match previous {
+            Some(previous) if previous.result_id == Some(params.previous_result_id) => Ok(Some(
+                SemanticTokensFullDeltaResult::TokensDelta(SemanticTokensDelta {
+                    result_id: tokens.result_id,
+                    edits: semantic_tokens::diff(&previous.data, &tokens.data),
+                }),
+            )),
+            _ => Ok(Some(SemanticTokensFullDeltaResult::Tokens(tokens))),
+        }
+    }
+
     async fn completion(
         &self,
-        _: CompletionParams,
+        params: CompletionParams,
     ) -> tower_lsp::jsonrpc::Result<Option<CompletionResponse>> {
-        Ok(None)
+        let Some(interpreter) = self
+            .get_interpreter(&params.text_document_position.text_document.uri)
+            .await
+        else {
+            return Ok(None);
+        };
+        let interpreter = interpreter.read().await;
+        let Some((file_id, byte)) = self.locate(&params.text_document_position, &interpreter)
+        else {
+            return Ok(None);
+        };
+        let text = &interpreter.get_file(file_id).text;
+        let line = &text[text[..byte].rfind('\n').map_or(0, |x| x + 1)..byte];
+
+        if let Some((_, path)) = line.rsplit_once("mod \"")
+            && !path.contains('"')
+        {
+            let items = interpreter
+                .get_module_paths()
+                .into_iter()
+                .map(|path| CompletionItem {
+                    label: path,
+                    kind: Some(CompletionItemKind::MODULE),
+                    ..Default::default()
+                })
+                .collect();
+            return Ok(Some(CompletionResponse::Array(items)));
+        }
+
+        let Some(scope_id) = interpreter.find_scope_at(file_id, byte) else {
+            return Ok(None);
+        };
+        let is_name_char = |x: char| x.is_alphanumeric() || x == '_';
+        let before_name = line.trim_end_matches(is_name_char);
+        let elements = if let Some(target) = before_name.strip_suffix('.') {
+            let target = &target[target
+                .trim_end_matches(|x| is_name_char(x) || x == '.')
+                .len()..];
+            let path = target.split('.').collect::<Vec<_>>();
+            let Some(scope_id) = interpreter.find_scope_by_path(scope_id, &path) else {
+                return Ok(None);
+            };
+            let mut elements = interpreter
+                .get(scope_id)
+                .elements
+                .iter()
+                .map(|(name, id)| (*name, *id))
+                .collect::<Vec<_>>();
+            elements.sort_by(|a, b| interpreter.id2str(a.0).cmp(&interpreter.id2str(b.0)));
+            elements
+        } else {
+            interpreter.get_visible_elements(scope_id)
+        };
+        let items = elements
+            .into_iter()
+            .map(|(name, element_id)| Self::make_completion_item(&interpreter, name, element_id))
+            .collect();
+        Ok(Some(CompletionResponse::Array(items)))
     }
 
     async fn did_close(&self, params: DidCloseTextDocumentParams) {
-        let mut files = self.opened_files.write().await;
         let uri = params.text_document.uri;
-        files.remove(&uri);
+        let Some(file) = self.opened_files.write().await.remove(&uri) else {
+            return;
+        };
+        {
+            let Some(interpreter) = self.get_interpreter(&uri).await else {
+                return;
+            };
+            let mut interpreter = interpreter.write().await;
+            let Some(file_id) = interpreter.find_file(&file.path) else {
+                return;
+            };
+            // unsaved changes are dropped with the buffer.
+            interpreter.update_file(file_id);
+            interpreter.run().await;
+        }
+        self.diagnose_opened_files().await;
+    }
+
+    async fn did_change_workspace_folders(&self, params: DidChangeWorkspaceFoldersParams) {
+        for folder in params.event.removed {
+            let Ok(folder) = folder.uri.to_file_path() else {
+                continue;
+            };
+            self.workspaces
+                .write()
+                .await
+                .retain(|workspace_path, _| !workspace_path.starts_with(&folder));
+        }
+        let mut added = vec![];
+        for folder in params.event.added {
+            added.extend(self.add_workspace_folder(&folder.uri).await);
+        }
+        for interpreter in added {
+            Self::load(&interpreter).await;
+        }
+        // opened files left out of any workspace aren't diagnosed anymore.
+        for uri in self.opened_files.read().await.keys() {
+            if self.get_interpreter(uri).await.is_none() {
+                self.client
+                    .publish_diagnostics(uri.clone(), vec![], None)
+                    .await;
+            }
+        }
+        self.diagnose_opened_files().await;
+    }
+
+    async fn did_change_watched_files(&self, params: DidChangeWatchedFilesParams) {
+        let mut changed = Vec::<Arc<RwLock<Interpreter>>>::new();
+        for change in params.changes {
+            let Some(interpreter) = self.get_interpreter(&change.uri).await else {
+                continue;
+            };
+            {
+                let mut interpreter = interpreter.write().await;
+                let Some(path) = self.uri2path(&change.uri, &interpreter) else {
+                    continue;
+                };
+                match change.typ {
+                    FileChangeType::CREATED => interpreter.add_src_modules(),
+                    FileChangeType::CHANGED => {
+                        // opened buffers are kept in sync by the editor instead.
+                        if self.opened_files.read().await.contains_key(&change.uri) {
+                            continue;
+                        }
+                        let Some(file_id) = interpreter.find_file(&path) else {
+                            continue;
+                        };
+                        interpreter.update_file(file_id);
+                    }
+                    FileChangeType::DELETED => {
+                        // a deleted directory takes every file under it.
+                        let file_ids = interpreter
+                            .files
+                            .iter()
+                            .filter(|(_, file)| file.path.starts_with(&path))
+                            .map(|(file_id, _)| file_id)
+                            .collect::<Vec<_>>();
+                        for file_id in file_ids {
+                            interpreter.remove_file(file_id);
+                        }
+                    }
+                    _ => continue,
+                }
+            }
+            if !changed.iter().any(|x| Arc::ptr_eq(x, &interpreter)) {
+                changed.push(interpreter);
+            }
+        }
+        for interpreter in &changed {
+            interpreter.write().await.run().await;
+        }
+        if !changed.is_empty() {
+            self.diagnose_opened_files().await;
+        }
     }
 }
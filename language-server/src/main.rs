@@ -2,6 +2,8 @@ use crate::language_server::LanguageServer;
 use tower_lsp::{LspService, Server};
 
 mod language_server;
+mod semantic_tokens;
+
 #[tokio::main]
 async fn main() {
     env_logger::init();
@@ -0,0 +1,156 @@
+use tower_lsp::lsp_types::{
+    SemanticToken, SemanticTokenModifier, SemanticTokenType, SemanticTokensEdit,
+    SemanticTokensLegend,
+};
+use type_sitter::HasChild as _;
+
+use moss_interpreter::{
+    interpreter::{
+        Id, Interpreter, InterpreterLike, Node as _, UntypedNode, element::Element, file::FileId,
+        value::Value,
+    },
+    utils::moss,
+};
+
+const TOKEN_TYPES: [SemanticTokenType; 5] = [
+    SemanticTokenType::FUNCTION,
+    SemanticTokenType::PARAMETER,
+    SemanticTokenType::VARIABLE,
+    SemanticTokenType::NAMESPACE,
+    SemanticTokenType::TYPE,
+];
+const FUNCTION: u32 = 0;
+const PARAMETER: u32 = 1;
+const VARIABLE: u32 = 2;
+const NAMESPACE: u32 = 3;
+const TYPE: u32 = 4;
+
+const TOKEN_MODIFIERS: [SemanticTokenModifier; 3] = [
+    SemanticTokenModifier::DECLARATION,
+    SemanticTokenModifier::DEFAULT_LIBRARY,
+    SemanticTokenModifier::new("error"),
+];
+const DECLARATION: u32 = 1 << 0;
+const DEFAULT_LIBRARY: u32 = 1 << 1;
+const ERROR: u32 = 1 << 2;
+
+pub fn legend() -> SemanticTokensLegend {
+    SemanticTokensLegend {
+        token_types: TOKEN_TYPES.to_vec(),
+        token_modifiers: TOKEN_MODIFIERS.to_vec(),
+    }
+}
+
+/// Token type and modifiers of a name whose element has `value`.
+fn classify(value: Option<Value>) -> (u32, u32) {
+    match value {
+        Some(Value::Function(_) | Value::FunctionBody(_)) => (FUNCTION, 0),
+        Some(Value::BuiltinFunction(_)) => (FUNCTION, DEFAULT_LIBRARY),
+        Some(Value::Param(_)) => (PARAMETER, 0),
+        Some(Value::Scope(_)) => (NAMESPACE, 0),
+        Some(
+            Value::IntType(_)
+            | Value::FloatType(_)
+            | Value::BoolType(_)
+            | Value::StringType(_)
+            | Value::ListType(_)
+            | Value::SetType(_)
+            | Value::ScopeType(_)
+            | Value::ElementType(_)
+            | Value::FunctionType(_)
+            | Value::TypeType(_),
+        ) => (TYPE, 0),
+        Some(Value::Error(_)) => (VARIABLE, ERROR),
+        _ => (VARIABLE, 0),
+    }
+}
+
+/// Classifies every name authored in `file_id`: keys by their element's value, finds by the
+/// value of the element they resolved to. Columns and lengths count bytes with `utf8`, UTF-16
+/// code units without.
+pub fn file_tokens(interpreter: &Interpreter, file_id: FileId, utf8: bool) -> Vec<SemanticToken> {
+    let mut tokens = Vec::<(UntypedNode<'static>, u32, u32)>::new();
+    let builtin_module = interpreter.builtin_module;
+    let classify_element = |element_id: Id<Element>| {
+        let (token_type, mut modifiers) = classify(interpreter.get_element_value(element_id));
+        if Some(interpreter.get(element_id).module) == builtin_module {
+            modifiers |= DEFAULT_LIBRARY;
+        }
+        (token_type, modifiers)
+    };
+    interpreter.visit_file_scopes(file_id, |scope_id| {
+        for element_id in interpreter.get(scope_id).temp_elements.iter().copied() {
+            let Some(source) = interpreter.get(element_id).source else {
+                continue;
+            };
+            if let Some(key) = source.key_source {
+                let (token_type, modifiers) = classify_element(element_id);
+                tokens.push((key.upcast(), token_type, modifiers | DECLARATION));
+            }
+            if let Ok(moss::ValueChild::Function(function)) = source.value_source.child()
+                && let Ok(param) = function.param()
+            {
+                tokens.push((param.upcast(), PARAMETER, DECLARATION));
+            }
+            if let Some(name) = interpreter.get_find_name_source(element_id) {
+                let (token_type, modifiers) = match interpreter.get_found_element(element_id) {
+                    Some(found_id) => classify_element(found_id),
+                    None => (VARIABLE, ERROR),
+                };
+                tokens.push((name, token_type, modifiers));
+            }
+        }
+    });
+    tokens.sort_by_key(|(node, ..)| node.start_byte());
+    tokens.dedup_by_key(|(node, ..)| node.start_byte());
+
+    let text = &interpreter.get_file(file_id).text;
+    let units = |text: &str| {
+        if utf8 {
+            text.len()
+        } else {
+            text.encode_utf16().count()
+        }
+    };
+    let mut encoded = Vec::with_capacity(tokens.len());
+    let (mut row, mut column) = (0, 0);
+    for (node, token_type, modifiers) in tokens {
+        let start = node.start_position();
+        let start_column = units(&text[node.start_byte() - start.column..node.start_byte()]);
+        let delta_line = start.row - row;
+        let delta_start = if delta_line == 0 {
+            start_column - column
+        } else {
+            start_column
+        };
+        (row, column) = (start.row, start_column);
+        encoded.push(SemanticToken {
+            delta_line: delta_line as u32,
+            delta_start: delta_start as u32,
+            length: units(&text[node.start_byte()..node.end_byte()]) as u32,
+            token_type,
+            token_modifiers_bitset: modifiers,
+        });
+    }
+    encoded
+}
+
+/// Edits turning `old` tokens into `new`, replacing the span between their common prefix and
+/// suffix. Offsets count integers, five per token.
+pub fn diff(old: &[SemanticToken], new: &[SemanticToken]) -> Vec<SemanticTokensEdit> {
+    let prefix = old.iter().zip(new).take_while(|(a, b)| a == b).count();
+    let suffix = old[prefix..]
+        .iter()
+        .rev()
+        .zip(new[prefix..].iter().rev())
+        .take_while(|(a, b)| a == b)
+        .count();
+    if prefix + suffix == old.len() && old.len() == new.len() {
+        return vec![];
+    }
+    vec![SemanticTokensEdit {
+        start: (prefix * 5) as u32,
+        delete_count: ((old.len() - prefix - suffix) * 5) as u32,
+        data: Some(new[prefix..new.len() - suffix].to_vec()),
+    }]
+}